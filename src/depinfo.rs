@@ -3,7 +3,12 @@ use blake3::Hash;
 use monostate::MustBe;
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+	cmp::Ordering,
+	collections::{BTreeMap, BTreeSet},
+	ffi::OsString,
+	path::{Path, PathBuf}
+};
 
 struct HashDef;
 
@@ -47,7 +52,7 @@ struct Dependency(
 
 impl Dependency {
 	fn new(crate_name: String, version: Option<Version>, lib_name: String) -> Self {
-		let lib_name = (lib_name != crate_name).then(|| lib_name);
+		let lib_name = (lib_name != crate_name).then_some(lib_name);
 		Self(crate_name, version, lib_name)
 	}
 
@@ -160,9 +165,25 @@ pub struct DependencyInfo(DependencyInfoImpl);
 impl DependencyInfo {
 	/// Return the current markdown version. This is just an internal number to track changes
 	/// to the markdown output, and does not correspond to any "official" markdown spec version.
+	///
+	/// Version 2 changed the markdown output in the following ways:
+	///  - hard line breaks are written as a trailing backslash instead of two spaces
+	///  - the `edition2024` flag is removed from code block info strings
+	///  - reference-style images are emitted as references instead of inline images
+	///  - link titles are kept in the link definitions
+	///  - in-document links to headings use the anchors generated by GitHub
+	///  - only code block flags that match a whole token of the info string are removed
 	#[inline]
 	pub fn markdown_version() -> u8 {
-		1
+		2
+	}
+
+	/// Return the path of the sidecar file that stores the dependency info for the
+	/// readme at `readme_path`, e.g. `README.md.doc2readme` for `README.md`.
+	pub fn sidecar_path(readme_path: &Path) -> PathBuf {
+		let mut path = OsString::from(readme_path);
+		path.push(".doc2readme");
+		path.into()
 	}
 
 	pub fn new(template: &str, rustdoc: &str) -> Self {
@@ -183,7 +204,15 @@ impl DependencyInfo {
 	}
 
 	pub fn check_input(&self, template: &str, rustdoc: &str) -> bool {
-		self.0.is_template_up2date(template) && self.0.is_rustdoc_up2date(rustdoc)
+		self.is_template_up2date(template) && self.is_rustdoc_up2date(rustdoc)
+	}
+
+	pub fn is_template_up2date(&self, template: &str) -> bool {
+		self.0.is_template_up2date(template)
+	}
+
+	pub fn is_rustdoc_up2date(&self, rustdoc: &str) -> bool {
+		self.0.is_rustdoc_up2date(rustdoc)
 	}
 
 	pub fn is_empty(&self) -> bool {
@@ -199,8 +228,10 @@ impl DependencyInfo {
 		self.0.add_dependency(crate_name, version, lib_name)
 	}
 
-	pub fn check_outdated(&self) -> bool {
-		self.0.markdown_version() != Self::markdown_version()
+	/// Compare the markdown version of the readme with the current one. A readme that
+	/// was created by a newer version of this tool compares as [`Ordering::Greater`].
+	pub fn compare_markdown_version(&self) -> Ordering {
+		self.0.markdown_version().cmp(&Self::markdown_version())
 	}
 
 	// TODO req probably doesn't need to be optional
@@ -240,9 +271,10 @@ impl DependencyInfo {
 
 #[cfg(test)]
 mod tests {
-	use super::DependencyInfo;
+	use super::{DependencyInfo, DependencyInfoImpl};
 	use base64::prelude::*;
 	use semver::Version;
+	use std::cmp::Ordering;
 
 	const TEMPLATE: &str = include_str!("README.j2");
 	const RUSTDOC: &str = "This is the best crate ever!";
@@ -311,4 +343,19 @@ mod tests {
 		assert!(dep_info.check_input(TEMPLATE, RUSTDOC));
 		assert!(dep_info.check_dependency("anyhow", Some(&req_1_0_1), "anyhow", false));
 	}
+
+	#[test]
+	fn test_markdown_version() {
+		let current = DependencyInfo::markdown_version();
+		let dep_info = DependencyInfo::new(TEMPLATE, RUSTDOC);
+		assert_eq!(dep_info.compare_markdown_version(), Ordering::Equal);
+
+		let older =
+			DependencyInfo(DependencyInfoImpl::new(current - 1, TEMPLATE, RUSTDOC));
+		assert_eq!(older.compare_markdown_version(), Ordering::Less);
+
+		let newer =
+			DependencyInfo(DependencyInfoImpl::new(current + 1, TEMPLATE, RUSTDOC));
+		assert_eq!(newer.compare_markdown_version(), Ordering::Greater);
+	}
 }
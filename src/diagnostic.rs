@@ -1,33 +1,115 @@
 use ariadne::{Color, Label, Report, ReportKind};
-use std::{io, ops::Range};
+use clap::ValueEnum;
+use std::{
+	ffi::OsString,
+	io,
+	ops::Range,
+	sync::atomic::{AtomicBool, Ordering}
+};
 
 pub type Span = Range<usize>;
 
+/// Whether diagnostics are printed with colors.
+static COLOR: AtomicBool = AtomicBool::new(true);
+
+/// Enable or disable colors for all diagnostics.
+pub fn set_color(color: bool) {
+	COLOR.store(color, Ordering::Relaxed);
+}
+
 fn config() -> ariadne::Config {
-	ariadne::Config::default().with_index_type(ariadne::IndexType::Byte)
+	ariadne::Config::default()
+		.with_index_type(ariadne::IndexType::Byte)
+		.with_color(COLOR.load(Ordering::Relaxed))
+}
+
+/// When to use colors for the output.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum ColorChoice {
+	/// Use colors if `NO_COLOR`, `CLICOLOR_FORCE` and `CLICOLOR` allow it and the
+	/// output is a terminal.
+	#[default]
+	Auto,
+	/// Always use colors.
+	Always,
+	/// Never use colors.
+	Never
+}
+
+impl ColorChoice {
+	/// Decide whether to use colors. An explicit choice takes precedence over the
+	/// environment variables, which take precedence over the terminal detection.
+	pub fn use_color<F>(self, var: F, is_terminal: bool) -> bool
+	where
+		F: Fn(&str) -> Option<OsString>
+	{
+		match self {
+			Self::Always => return true,
+			Self::Never => return false,
+			Self::Auto => {}
+		}
+		// https://no-color.org/
+		if var("NO_COLOR").map_or(false, |value| !value.is_empty()) {
+			return false;
+		}
+		// https://bixense.com/clicolors/
+		if var("CLICOLOR_FORCE").map_or(false, |value| !value.is_empty() && value != "0")
+		{
+			return true;
+		}
+		if var("CLICOLOR").map_or(false, |value| value == "0") {
+			return false;
+		}
+		is_terminal
+	}
 }
 
 pub struct Diagnostic {
 	filename: String,
 	code: String,
+	/// The byte offset at which each line of the code starts.
+	line_starts: Vec<usize>,
 	reports: Vec<Report<'static, (String, Span)>>,
-	fail: bool
+	fail: bool,
+	warned: bool,
+	broken_links: bool
 }
 
 impl Diagnostic {
 	pub fn new(filename: String, code: String) -> Self {
+		let line_starts = std::iter::once(0)
+			.chain(code.match_indices('\n').map(|(idx, _)| idx + 1))
+			.collect();
 		Self {
 			filename,
 			code,
+			line_starts,
 			reports: Vec::new(),
-			fail: false
+			fail: false,
+			warned: false,
+			broken_links: false
 		}
 	}
 
+	/// Create a new diagnostic without any reports for the same file and code.
+	pub fn empty_clone(&self) -> Self {
+		Self::new(self.filename.clone(), self.code.clone())
+	}
+
 	pub fn is_fail(&self) -> bool {
 		self.fail
 	}
 
+	/// Return true if any warning was reported.
+	pub fn has_warnings(&self) -> bool {
+		self.warned
+	}
+
+	/// Return true if any link could not be resolved.
+	pub fn has_broken_links(&self) -> bool {
+		self.broken_links
+	}
+
 	pub fn print(&self) -> io::Result<()> {
 		self.print_to(io::stderr())
 	}
@@ -41,16 +123,20 @@ impl Diagnostic {
 	}
 
 	fn offset(&self, at: proc_macro2::LineColumn) -> usize {
-		let line_offset: usize = self
-			.code
-			.split('\n')
-			.take(at.line - 1)
-			.map(|line| line.len() + 1)
-			.sum();
+		let line_offset = self
+			.line_starts
+			.get(at.line - 1)
+			.copied()
+			.unwrap_or(self.code.len() + 1);
 		line_offset + at.column
 	}
 
-	fn offset_span(&self, span: proc_macro2::Span) -> Range<usize> {
+	/// Return the code that the diagnostics refer to.
+	pub(crate) fn code(&self) -> &str {
+		&self.code
+	}
+
+	pub(crate) fn offset_span(&self, span: proc_macro2::Span) -> Range<usize> {
 		self.offset(span.start()) .. self.offset(span.end())
 	}
 
@@ -81,6 +167,7 @@ impl Diagnostic {
 				.with_message(msg)
 				.finish()
 		);
+		self.warned = true;
 	}
 
 	/// Warning with a code label.
@@ -97,6 +184,7 @@ impl Diagnostic {
 				.with_label(Label::new((self.filename.clone(), span)).with_message(label))
 				.finish()
 		);
+		self.warned = true;
 	}
 
 	/// Warning that says that a macro was not expanded and helps to fix it.
@@ -110,6 +198,50 @@ impl Diagnostic {
 			.with_help("You can use `--expand-macros` on a nightly Rust toolchain to expand macros.")
 			.finish()
 		);
+		self.warned = true;
+	}
+
+	/// Warning that says that a doc comment is rendered as an indented code block. The
+	/// label is placed on the first line of the code block, if its span is known.
+	pub fn warn_indented_code_block(&mut self, span: Option<Span>) {
+		let mut report = Report::build(
+			ReportKind::Warning,
+			(self.filename.clone(), span.clone().unwrap_or(0 .. 0))
+		)
+		.with_config(config())
+		.with_message("Doc comment contains an indented code block")
+		.with_help("Check the indentation of the doc comment, or use a fenced code block if this is intended.");
+		if let Some(span) = span {
+			report.add_label(
+				Label::new((self.filename.clone(), span))
+					.with_message("This line is indented by four or more spaces")
+			);
+		}
+		self.reports.push(report.finish());
+		self.warned = true;
+	}
+
+	/// Warning that says that a link from the rustdoc could not be resolved. The label is
+	/// placed on the link, if its span is known.
+	pub fn warn_broken_link(&mut self, path: &str, first: &str, span: Option<Span>) {
+		let mut report = Report::build(
+			ReportKind::Warning,
+			(self.filename.clone(), span.clone().unwrap_or(0 .. 0))
+		)
+		.with_config(config())
+		.with_message(format_args!(
+			"Unable to resolve link to `{path}` as `{first}` is neither in scope nor a dependency"
+		))
+		.with_help("Check the spelling of the link, or add the crate that contains the item as a dependency.");
+		if let Some(span) = span {
+			report.add_label(
+				Label::new((self.filename.clone(), span))
+					.with_message("This link will most likely be broken")
+			);
+		}
+		self.reports.push(report.finish());
+		self.warned = true;
+		self.broken_links = true;
 	}
 
 	/// Syntax error with the code span from syn's error.
@@ -126,6 +258,30 @@ impl Diagnostic {
 		self.fail = true;
 	}
 
+	/// Error from rendering the template, labelled with the location in the template if
+	/// known.
+	pub fn template_error(&mut self, err: &minijinja::Error) {
+		let span = err.range().unwrap_or(0 .. 0);
+		let mut report =
+			Report::build(ReportKind::Error, (self.filename.clone(), span.clone()))
+				.with_config(config())
+				.with_message(match err.line() {
+					Some(line) => format!("Template Error on line {line}"),
+					None => "Template Error".into()
+				});
+		let msg = err
+			.detail()
+			.map(String::from)
+			.unwrap_or_else(|| err.kind().to_string());
+		if err.range().is_some() {
+			report.add_label(Label::new((self.filename.clone(), span)).with_message(msg));
+		} else {
+			report.set_note(msg);
+		}
+		self.reports.push(report.finish());
+		self.fail = true;
+	}
+
 	/// Error without a code label.
 	pub fn error<T>(&mut self, msg: T)
 	where
@@ -140,3 +296,100 @@ impl Diagnostic {
 		self.fail = true;
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{ColorChoice, Diagnostic};
+	use proc_macro2::LineColumn;
+	use std::ffi::OsString;
+
+	#[test]
+	fn offset() {
+		let diag = Diagnostic::new("lib.rs".into(), "//! a\n\n//! bc\n".into());
+		let offset = |line, column| diag.offset(LineColumn { line, column });
+		assert_eq!(offset(1, 0), 0);
+		assert_eq!(offset(1, 4), 4);
+		assert_eq!(offset(2, 0), 6);
+		assert_eq!(offset(3, 5), 12);
+		assert_eq!(offset(4, 0), 14);
+	}
+
+	#[test]
+	fn offset_many_lines() {
+		let lines = 100_000;
+		let code = "//! line\n".repeat(lines);
+		let diag = Diagnostic::new("lib.rs".into(), code);
+		for line in 1 ..= lines {
+			assert_eq!(
+				diag.offset(LineColumn { line, column: 4 }),
+				(line - 1) * 9 + 4
+			);
+		}
+	}
+
+	fn use_color(choice: ColorChoice, vars: &[(&str, &str)], is_terminal: bool) -> bool {
+		choice.use_color(
+			|name| {
+				vars.iter()
+					.find(|(key, _)| *key == name)
+					.map(|(_, value)| OsString::from(value))
+			},
+			is_terminal
+		)
+	}
+
+	#[test]
+	fn color_choice() {
+		assert!(use_color(ColorChoice::Auto, &[], true));
+		assert!(!use_color(ColorChoice::Auto, &[], false));
+		assert!(use_color(ColorChoice::Always, &[("NO_COLOR", "1")], false));
+		assert!(!use_color(
+			ColorChoice::Never,
+			&[("CLICOLOR_FORCE", "1")],
+			true
+		));
+	}
+
+	#[test]
+	fn color_env() {
+		assert!(!use_color(ColorChoice::Auto, &[("NO_COLOR", "1")], true));
+		assert!(use_color(ColorChoice::Auto, &[("NO_COLOR", "")], true));
+		assert!(!use_color(
+			ColorChoice::Auto,
+			&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")],
+			true
+		));
+		assert!(use_color(
+			ColorChoice::Auto,
+			&[("CLICOLOR_FORCE", "1")],
+			false
+		));
+		assert!(!use_color(
+			ColorChoice::Auto,
+			&[("CLICOLOR_FORCE", "0")],
+			false
+		));
+		assert!(!use_color(ColorChoice::Auto, &[("CLICOLOR", "0")], true));
+		assert!(use_color(
+			ColorChoice::Auto,
+			&[("CLICOLOR", "0"), ("CLICOLOR_FORCE", "1")],
+			false
+		));
+		assert!(use_color(ColorChoice::Auto, &[("CLICOLOR", "1")], true));
+	}
+
+	#[test]
+	fn broken_link() {
+		let mut diag = Diagnostic::new("lib.rs".into(), "//! [`missing`]\n".into());
+		assert!(!diag.has_broken_links());
+		diag.warn("not a broken link");
+		assert!(!diag.has_broken_links());
+		diag.warn_broken_link("missing", "missing", Some(4 .. 15));
+		assert!(diag.has_broken_links());
+
+		let mut out = Vec::new();
+		diag.print_to(&mut out).unwrap();
+		let out = String::from_utf8(out).unwrap();
+		assert!(out.contains("This link will most likely be broken"));
+	}
+}
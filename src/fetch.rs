@@ -0,0 +1,66 @@
+//! Download templates from a url instead of reading them from a file.
+
+use log::warn;
+use std::{env, fs, path::PathBuf};
+
+/// The file that caches the template downloaded from `url`.
+fn cache_path(url: &str) -> PathBuf {
+	env::temp_dir()
+		.join("cargo-doc2readme")
+		.join(format!("{}.j2", blake3::hash(url.as_bytes()).to_hex()))
+}
+
+#[cfg(feature = "network")]
+fn download(url: &str) -> anyhow::Result<String> {
+	Ok(ureq::get(url).call()?.into_string()?)
+}
+
+#[cfg(not(feature = "network"))]
+fn download(_url: &str) -> anyhow::Result<String> {
+	anyhow::bail!("cargo-doc2readme was built without the `network` feature")
+}
+
+/// Return true if the template path is a url that needs to be downloaded.
+pub(crate) fn is_url(path: &str) -> bool {
+	path.starts_with("https://") || path.starts_with("http://")
+}
+
+/// Download the template from `url`. If that fails, the template from the last
+/// successful download is used, or `None` is returned if there is none.
+pub(crate) fn fetch_template(url: &str) -> Option<String> {
+	let cache = cache_path(url);
+	match download(url) {
+		Ok(template) => {
+			let cached = cache
+				.parent()
+				.map_or(Ok(()), fs::create_dir_all)
+				.and_then(|_| fs::write(&cache, &template));
+			if let Err(err) = cached {
+				warn!("Unable to cache template in {}: {err}", cache.display());
+			}
+			Some(template)
+		},
+		Err(err) => {
+			warn!("Unable to download template from {url}: {err}");
+			let template = fs::read_to_string(&cache).ok()?;
+			warn!("Using the template downloaded previously");
+			Some(template)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{fetch_template, is_url};
+
+	#[test]
+	fn template_url() {
+		assert!(is_url("https://example.org/README.j2"));
+		assert!(!is_url("README.j2"));
+	}
+
+	#[test]
+	fn fetch_unreachable_template() {
+		assert_eq!(fetch_template("http://127.0.0.1:1/README.j2"), None);
+	}
+}
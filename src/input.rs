@@ -1,26 +1,33 @@
-use crate::{diagnostic::Diagnostic, preproc::Preprocessor};
+use crate::{diagnostic::Diagnostic, preproc::Preprocessor, InputOptions};
 use anyhow::{bail, Context};
-use cargo_metadata::{Edition, Metadata, Package, Target};
+use cargo_metadata::{DependencyKind, Edition, Metadata, Package, Target};
 use either::Either;
 use log::{debug, info};
-use proc_macro2::{Span, TokenStream, TokenTree};
+use proc_macro2::Span;
+use pulldown_cmark::{CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
 use quote::ToTokens as _;
 use semver::{Comparator, Op, Version, VersionReq};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::{
 	collections::{HashMap, HashSet, VecDeque},
 	fmt::{self, Debug, Formatter},
-	fs::File,
+	fs::{self, File},
 	io::{self, BufReader, Cursor, Read, Write},
+	ops::Range,
 	path::Path,
-	process::{Command, Output}
+	process::{Command, Output, Stdio},
+	thread,
+	time::Duration
 };
 use syn::{
+	ext::IdentExt as _,
 	parse::{Parse, ParseStream},
+	punctuated::Punctuated,
 	spanned::Spanned as _,
-	Expr, ExprLit, Ident, Item, ItemMacro, ItemUse, Lit, LitStr, Macro, Meta, Token,
-	UsePath, UseTree, Visibility
+	Attribute, Expr, ExprLit, Ident, Item, ItemMacro, ItemUse, Lit, LitStr, Macro, Meta,
+	MetaList, Token, UsePath, UseTree, Visibility
 };
+use wait_timeout::ChildExt as _;
 
 type ScopeScope = HashMap<String, VecDeque<(LinkType, String)>>;
 
@@ -29,7 +36,9 @@ pub struct Scope {
 	// use statements and declared items. maps name to path.
 	pub scope: ScopeScope,
 	// private modules so that `pub use`'d items are considered inlined.
-	pub privmods: HashSet<String>
+	pub privmods: HashSet<String>,
+	// public items inside of inline modules. maps full path to link type.
+	pub items: HashMap<String, LinkType>
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
@@ -57,14 +66,15 @@ pub enum LinkType {
 }
 
 fn make_prelude<const N: usize>(
+	krate: &str,
 	prelude: [(&'static str, &'static str, LinkType); N]
 ) -> ScopeScope {
 	prelude
 		.into_iter()
 		.flat_map(|(name, path, link_type)| {
 			let path = match path {
-				"" => format!("::std::{name}"),
-				_ => format!("::std::{path}::{name}")
+				"" => format!("::{krate}::{name}"),
+				_ => format!("::{krate}::{path}::{name}")
 			};
 			let items: VecDeque<_> = [(link_type, path)].into_iter().collect();
 			match link_type {
@@ -79,6 +89,7 @@ fn make_prelude<const N: usize>(
 }
 
 impl Scope {
+	#[allow(clippy::unwrap_or_default)]
 	fn insert<K, V>(&mut self, key: K, ty: LinkType, value: V)
 	where
 		K: Into<String>,
@@ -93,14 +104,15 @@ impl Scope {
 	pub(crate) fn empty() -> Self {
 		Self {
 			scope: HashMap::new(),
-			privmods: HashSet::new()
+			privmods: HashSet::new(),
+			items: HashMap::new()
 		}
 	}
 
 	/// Create a new scope from the Rust prelude.
 	pub fn prelude(edition: Edition) -> Self {
 		let mut scope = Self {
-			scope: make_prelude([
+			scope: make_prelude("std", [
 				// https://doc.rust-lang.org/stable/std/primitive/index.html#reexports
 				("bool", "", LinkType::Primitive),
 				("char", "", LinkType::Primitive),
@@ -194,12 +206,13 @@ impl Scope {
 				("write", "", LinkType::Macro),
 				("writeln", "", LinkType::Macro)
 			]),
-			privmods: HashSet::new()
+			privmods: HashSet::new(),
+			items: HashMap::new()
 		};
 
 		if edition >= Edition::E2021 {
 			// https://blog.rust-lang.org/2021/05/11/edition-2021.html#additions-to-the-prelude
-			for (key, value) in make_prelude([
+			for (key, value) in make_prelude("std", [
 				("TryInto", "convert", LinkType::Use),
 				("TryFrom", "convert", LinkType::Use),
 				("FromIterator", "iter", LinkType::Use)
@@ -208,8 +221,39 @@ impl Scope {
 			}
 		}
 
+		if edition >= Edition::_E2024 {
+			// https://doc.rust-lang.org/edition-guide/rust-2024/prelude.html
+			for (key, value) in make_prelude("std", [
+				("Future", "future", LinkType::Use),
+				("IntoFuture", "future", LinkType::Use)
+			]) {
+				scope.scope.insert(key, value);
+			}
+		}
+
 		scope
 	}
+
+	/// Add the common types of the `proc_macro` crate to the scope. This is used for
+	/// proc-macro crates, which have access to the `proc_macro` crate.
+	pub fn add_proc_macro_prelude(&mut self) {
+		// https://doc.rust-lang.org/stable/proc_macro/index.html
+		for (key, value) in make_prelude("proc_macro", [
+			("Delimiter", "", LinkType::Enum),
+			("Group", "", LinkType::Struct),
+			("Ident", "", LinkType::Struct),
+			("Literal", "", LinkType::Struct),
+			("Punct", "", LinkType::Struct),
+			("Spacing", "", LinkType::Enum),
+			("Span", "", LinkType::Struct),
+			("TokenStream", "", LinkType::Struct),
+			("TokenTree", "", LinkType::Enum)
+		]) {
+			for (link_type, path) in value {
+				self.insert(key.clone(), link_type, path);
+			}
+		}
+	}
 }
 
 #[derive(Debug)]
@@ -236,48 +280,53 @@ impl CrateCode {
 
 	pub fn read_expansion<P>(
 		manifest_path: Option<P>,
-		package: Option<String>,
 		target: &Target,
-		features: Option<String>,
-		no_default_features: bool,
-		all_features: bool
+		options: &InputOptions
 	) -> anyhow::Result<CrateCode>
 	where
 		P: AsRef<Path>
 	{
-		let mut cmd = Command::new("cargo");
-		cmd.arg("+nightly").arg("rustc");
-		if let Some(manifest_path) = manifest_path {
-			cmd.arg("--manifest-path").arg(manifest_path.as_ref());
-		}
-		if let Some(package) = package.as_deref() {
-			cmd.arg("-p").arg(package);
-		}
-		if let Some(features) = features {
-			cmd.arg("--features").arg(features);
-		}
-		if no_default_features {
-			cmd.arg("--no-default-features");
-		}
-		if all_features {
-			cmd.arg("--all-features");
-		}
-		if target.is_lib() {
+		let mut cmd = cargo_command(&["+nightly", "rustc"], manifest_path, options);
+		if is_lib_target(target) {
 			cmd.arg("--lib");
 		} else if target.is_bin() {
 			cmd.arg("--bin").arg(&target.name);
+		} else if target.is_example() {
+			cmd.arg("--example").arg(&target.name);
+		}
+		if let Some(profile) = options.profile.as_deref() {
+			cmd.arg("--profile").arg(profile);
 		}
 		cmd.arg("--").arg("-Zunpretty=expanded");
 
 		info!("Running rustc -Zunpretty=expanded");
-		let Output {
+		let output = match options.expand_timeout {
+			Some(timeout) => output_with_timeout(&mut cmd, timeout),
+			None => cmd.output().map(Some)
+		}
+		.context("Failed to run cargo to expand crate content")?;
+		let Some(Output {
 			stdout,
 			stderr,
 			status
-		} = cmd.output()
-			.context("Failed to run cargo to expand crate content")?;
+		}) = output
+		else {
+			bail!(
+				"Cargo did not finish expanding the macros within {} seconds",
+				options.expand_timeout.unwrap_or_default().as_secs()
+			);
+		};
 
 		if !status.success() {
+			// The most likely cause for first-time users is a missing nightly toolchain, in
+			// which case cargo's output is not very helpful.
+			if is_nightly_missing(&stderr) {
+				bail!(
+					"Expanding macros requires a nightly toolchain, but none is installed. \
+					 You can install one using `rustup toolchain install nightly`."
+				);
+			}
+
 			// Something bad happened during the compilation. Let's print
 			// anything Cargo reported to us and return an error.
 			io::stdout()
@@ -292,6 +341,158 @@ impl CrateCode {
 	}
 }
 
+/// Create a cargo command with the given subcommand, that passes the package, features
+/// and target directory from the options to cargo.
+fn cargo_command<P>(
+	subcommand: &[&str],
+	manifest_path: Option<P>,
+	options: &InputOptions
+) -> Command
+where
+	P: AsRef<Path>
+{
+	let mut cmd = Command::new("cargo");
+	cmd.args(subcommand);
+	if let Some(manifest_path) = manifest_path {
+		cmd.arg("--manifest-path").arg(manifest_path.as_ref());
+	}
+	if let Some(package) = options.package.as_deref() {
+		cmd.arg("-p").arg(package);
+	}
+	if let Some(features) = options.features.as_deref() {
+		cmd.arg("--features").arg(features);
+	}
+	if options.no_default_features {
+		cmd.arg("--no-default-features");
+	}
+	if options.all_features {
+		cmd.arg("--all-features");
+	}
+	if let Some(target_dir) = options.target_dir.as_deref() {
+		cmd.arg("--target-dir").arg(target_dir);
+	}
+	cmd
+}
+
+/// Run the doc tests of the library target using `cargo test --doc`, and report an
+/// error for every doc test that fails.
+pub fn verify_doctests<P>(
+	manifest_path: Option<P>,
+	target: &Target,
+	options: &InputOptions,
+	diagnostics: &mut Diagnostic
+) where
+	P: AsRef<Path>
+{
+	if !is_lib_target(target) {
+		diagnostics.warn("Doc tests can only be verified for library targets");
+		return;
+	}
+
+	let mut cmd = cargo_command(&["test", "--doc"], manifest_path, options);
+	info!("Running cargo test --doc");
+	let Output {
+		stdout,
+		stderr,
+		status
+	} = match cmd.output() {
+		Ok(output) => output,
+		Err(e) => {
+			diagnostics
+				.error(format!("Failed to run cargo to verify the doc tests: {e}"));
+			return;
+		}
+	};
+	if status.success() {
+		return;
+	}
+
+	// let the user see what went wrong, just like when expanding macros
+	let mut out = io::stdout().lock();
+	out.write_all(&stderr)
+		.and_then(|_| out.write_all(&stdout))
+		.expect("Failed to write cargo output to stdout");
+
+	let stdout = String::from_utf8_lossy(&stdout);
+	let failures = failed_tests(&stdout);
+	if failures.is_empty() {
+		diagnostics.error("Cargo failed to run the doc tests");
+	}
+	for test in failures {
+		diagnostics.error(format!("Doc test failed: {test}"));
+	}
+}
+
+/// Return the names of the failed tests from the output of the test harness, which lists
+/// them at the end of its output.
+fn failed_tests(stdout: &str) -> Vec<&str> {
+	let Some(idx) = stdout.rfind("\nfailures:\n") else {
+		return Vec::new();
+	};
+	stdout[idx + "\nfailures:\n".len() ..]
+		.lines()
+		.take_while(|line| line.starts_with("    "))
+		.map(str::trim)
+		.collect()
+}
+
+/// Run the command and collect its output like [`Command::output`], but kill it if it
+/// does not finish within `timeout`. Returns `None` if the command was killed.
+fn output_with_timeout(
+	cmd: &mut Command,
+	timeout: Duration
+) -> io::Result<Option<Output>> {
+	let mut child = cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+
+	// read the output in the background, otherwise the child might block on a full pipe
+	let read = |mut pipe: Box<dyn Read + Send>| {
+		thread::spawn(move || {
+			let mut buf = Vec::new();
+			pipe.read_to_end(&mut buf).map(|_| buf)
+		})
+	};
+	let stdout = read(Box::new(child.stdout.take().unwrap()));
+	let stderr = read(Box::new(child.stderr.take().unwrap()));
+
+	let Some(status) = child.wait_timeout(timeout)? else {
+		// the reader threads are not joined as the pipes might still be held open by
+		// processes that cargo spawned
+		child.kill()?;
+		child.wait()?;
+		return Ok(None);
+	};
+	Ok(Some(Output {
+		status,
+		stdout: stdout.join().unwrap()?,
+		stderr: stderr.join().unwrap()?
+	}))
+}
+
+/// Return true if cargo's output indicates that the nightly toolchain is not available.
+fn is_nightly_missing(stderr: &[u8]) -> bool {
+	let stderr = String::from_utf8_lossy(stderr);
+	// rustup reports "toolchain 'nightly-<triple>' is not installed", and cargo
+	// reports "no such command: `+nightly`" if it is not managed by rustup
+	(stderr.contains("toolchain 'nightly") && stderr.contains("is not installed"))
+		|| stderr.contains("no such command: `+nightly`")
+}
+
+/// Return true if this target is a library target. Unlike [`Target::is_lib`], this
+/// includes proc-macro targets and libraries with a non-default crate type like
+/// `cdylib`.
+pub fn is_lib_target(target: &Target) -> bool {
+	target.kind.iter().any(|kind| {
+		matches!(
+			kind.as_str(),
+			"lib" | "rlib" | "dylib" | "cdylib" | "staticlib" | "proc-macro"
+		)
+	})
+}
+
+fn is_proc_macro_target(target: &Target) -> bool {
+	target.kind.iter().any(|kind| kind == "proc-macro")
+}
+
 #[derive(Clone, Copy, Debug, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TargetType {
@@ -299,10 +500,101 @@ pub enum TargetType {
 	Lib
 }
 
+/// How the default template lays out the badges.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BadgeStyle {
+	/// All badges on one line next to the title.
+	#[default]
+	Row,
+	/// Every badge on its own line below the title.
+	Stacked
+}
+
+/// The `[package.metadata.doc2readme]` section of the `Cargo.toml`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct PackageMetadata {
+	/// The title of the readme, defaults to the crate name.
+	pub title: Option<String>,
+	/// The version of the crate that is required for the documented features.
+	pub min_version: Option<String>,
+	/// Codeblock flags that are removed from the fence language in addition to those
+	/// known to rustdoc.
+	#[serde(default)]
+	pub codeblock_flags: Vec<String>,
+	/// Names that are removed from the scope and never turned into links.
+	#[serde(default)]
+	pub ignore_links: Vec<String>,
+	/// Whether the default template adds a sentence with the minimum supported Rust
+	/// version after the readme.
+	#[serde(default)]
+	pub msrv_note: bool,
+	/// How the default template lays out the badges.
+	#[serde(default)]
+	pub badge_style: BadgeStyle,
+	/// Features that are activated in addition to those from `--features`, unless
+	/// `--no-default-features` is used.
+	#[serde(default)]
+	pub features: Vec<String>,
+	/// Whether glob uses are accepted as a known limitation of the link generation
+	/// instead of warning about them.
+	#[serde(default)]
+	pub allow_glob_use: bool
+}
+
+impl PackageMetadata {
+	fn read(pkg: &Package, diagnostics: &mut Diagnostic) -> Self {
+		let Some(value) = pkg.metadata.get("doc2readme") else {
+			return Self::default();
+		};
+		match Self::deserialize(value) {
+			Ok(metadata) => metadata,
+			Err(err) => {
+				diagnostics.warn(format_args!(
+					"Unable to parse [package.metadata.doc2readme]: {err}"
+				));
+				Self::default()
+			}
+		}
+	}
+
+	/// Read only the features from the package metadata. Errors are ignored, they are
+	/// reported once the metadata is read together with the code.
+	pub(crate) fn read_features(pkg: &Package) -> Vec<String> {
+		pkg.metadata
+			.get("doc2readme")
+			.and_then(|value| Self::deserialize(value).ok())
+			.map(|metadata| metadata.features)
+			.unwrap_or_default()
+	}
+}
+
+/// Merge the space or comma separated list of features from the command line with the
+/// features from the package metadata. Returns `None` if there are no features at all.
+pub(crate) fn merge_features(
+	features: Option<&str>,
+	metadata_features: &[String]
+) -> Option<String> {
+	let mut merged: Vec<&str> = Vec::new();
+	for feature in features
+		.into_iter()
+		.flat_map(|features| features.split([' ', ',']))
+		.chain(metadata_features.iter().map(String::as_str))
+	{
+		if !feature.is_empty() && !merged.contains(&feature) {
+			merged.push(feature);
+		}
+	}
+	(!merged.is_empty()).then(|| merged.join(","))
+}
+
 #[derive(Debug)]
 pub struct InputFile {
 	/// The name of the crate.
 	pub crate_name: String,
+	/// The name of the crate's library, as used in rust code.
+	pub lib_name: String,
 	/// The version of the crate
 	pub crate_version: Version,
 	/// The target type.
@@ -311,10 +603,20 @@ pub struct InputFile {
 	pub repository: Option<String>,
 	/// The license field (if specified).
 	pub license: Option<String>,
+	/// The authors of the crate.
+	pub authors: Vec<String>,
 	/// The rust_version field (if specified).
 	pub rust_version: Option<Version>,
+	/// The keywords of the crate.
+	pub keywords: Vec<String>,
+	/// The crates.io categories of the crate.
+	pub categories: Vec<String>,
+	/// The `[package.metadata.doc2readme]` section.
+	pub metadata: PackageMetadata,
 	/// The unmodified rustdoc string
 	pub rustdoc: String,
+	/// The location of the rustdoc in the code.
+	pub rustdoc_spans: DocSpans,
 	/// The crate-level dependencies, mapping the valid identifier in rust code to the (possibly
 	/// renamed, containing invalid characters, etc.) crate name and version.
 	pub dependencies: HashMap<String, Dependency>,
@@ -326,24 +628,50 @@ pub struct Dependency {
 	/// The crate name as it appears on crates.io.
 	pub crate_name: String,
 
+	/// The name of the crate's library as it appears in the docs.rs url.
+	pub lib_name: String,
+
 	/// The version requirement of the dependency.
 	pub req: VersionReq,
 
 	/// The exact version of the dependency.
-	pub version: Version
+	pub version: Version,
+
+	/// Whether the dependency is a member of the current workspace.
+	pub workspace_member: bool,
+
+	/// The kind of the dependency, i.e. normal, dev or build dependency.
+	pub kind: DependencyKind
 }
 
 impl Dependency {
 	pub fn new(crate_name: String, req: VersionReq, version: Version) -> Self {
 		Self {
+			lib_name: sanitize_crate_name(&crate_name),
 			crate_name,
 			req,
-			version
+			version,
+			workspace_member: false,
+			kind: DependencyKind::Normal
 		}
 	}
 
-	pub fn as_tuple(&self) -> (&str, Option<&Version>) {
-		(self.crate_name.as_str(), Some(&self.version))
+	/// Use a library name other than the sanitized crate name.
+	pub fn with_lib_name(mut self, lib_name: String) -> Self {
+		self.lib_name = lib_name;
+		self
+	}
+
+	/// Use a dependency kind other than a normal dependency.
+	pub fn with_kind(mut self, kind: DependencyKind) -> Self {
+		self.kind = kind;
+		self
+	}
+
+	/// Mark the dependency as a member of the current workspace.
+	pub fn with_workspace_member(mut self, workspace_member: bool) -> Self {
+		self.workspace_member = workspace_member;
+		self
 	}
 }
 
@@ -360,15 +688,22 @@ impl Debug for Dependency {
 pub fn read_code(
 	metadata: &Metadata,
 	pkg: &Package,
+	target: &Target,
 	code: CrateCode,
 	target_type: TargetType,
+	options: &InputOptions,
 	diagnostics: &mut Diagnostic
 ) -> InputFile {
 	let crate_name = pkg.name.clone();
+	let lib_name = lib_name(pkg);
 	let crate_version = pkg.version.clone();
 	let repository = pkg.repository.clone();
 	let license = pkg.license.clone();
+	let authors = pkg.authors.clone();
 	let rust_version = pkg.rust_version.clone();
+	let keywords = pkg.keywords.clone();
+	let categories = pkg.categories.clone();
+	let package_metadata = PackageMetadata::read(pkg, diagnostics);
 
 	debug!("Reading code \n{}", code.0);
 	let file = match syn::parse_file(code.0.as_str()) {
@@ -379,56 +714,349 @@ pub fn read_code(
 		}
 	};
 
-	let rustdoc = read_rustdoc_from_file(&file, diagnostics);
+	// relative paths in `include_str!` are resolved relative to the source file
+	let src_dir = target
+		.src_path
+		.parent()
+		.map(|dir| dir.as_std_path())
+		.unwrap_or_else(|| Path::new("."));
+	let mut rustdoc = match &options.doc_from {
+		Some(module) => {
+			let path = module
+				.trim_start_matches("crate::")
+				.split("::")
+				.collect::<Vec<_>>();
+			read_rustdoc_from_module(&file.items, src_dir, src_dir, &path, diagnostics)
+				.unwrap_or_else(|| {
+					diagnostics
+						.error(format_args!("Unable to find the module `{module}`"));
+					Rustdoc::default()
+				})
+		},
+		None => read_rustdoc_from_file(&file, src_dir, diagnostics)
+	};
+	if options.include_reexport_docs {
+		read_reexport_docs_from_file(&file, src_dir, &mut rustdoc, diagnostics);
+	}
+	let mut fallback_item = None;
+	if options.fallback_to_item && rustdoc.doc.trim().is_empty() {
+		if let Some((doc, ident, link_type)) =
+			read_rustdoc_from_first_item(&file, src_dir, diagnostics)
+		{
+			diagnostics.info(format_args!(
+				"The crate has no documentation, using the documentation of `{ident}` instead"
+			));
+			rustdoc = doc;
+			fallback_item = Some((ident, link_type));
+		}
+	}
+	warn_indented_code_blocks(&rustdoc, diagnostics);
 	let dependencies = resolve_dependencies(metadata, pkg, diagnostics);
-	let scope = read_scope_from_file(pkg, &file, diagnostics);
+	let proc_macro = matches!(target_type, TargetType::Lib)
+		&& pkg.targets.iter().any(is_proc_macro_target);
+	let mut scope = read_scope_from_file(
+		pkg,
+		&file,
+		proc_macro,
+		options,
+		&package_metadata,
+		diagnostics
+	);
+	// links to `Self` in the documentation of an item point to the item itself
+	if let Some((ident, link_type)) = fallback_item {
+		scope.insert("Self", link_type, format!("::{lib_name}::{ident}"));
+	}
 
 	InputFile {
 		crate_name,
+		lib_name,
 		crate_version,
 		target_type,
 		repository,
 		license,
+		authors,
 		rust_version,
-		rustdoc,
+		keywords,
+		categories,
+		metadata: package_metadata,
+		rustdoc: rustdoc.doc,
+		rustdoc_spans: rustdoc.spans,
 		dependencies,
 		scope
 	}
 }
 
-fn read_rustdoc_from_file(file: &syn::File, diagnostics: &mut Diagnostic) -> String {
-	let mut doc = String::new();
-	for attr in &file.attrs {
+/// The location in the code of the doc attributes that the rustdoc was read from.
+#[derive(Debug, Default)]
+pub struct DocSpans(Vec<DocSpan>);
+
+#[derive(Debug)]
+struct DocSpan {
+	/// The range of the text of the doc attribute in the rustdoc.
+	doc: Range<usize>,
+	/// The range of the doc attribute in the code.
+	code: Range<usize>,
+	/// Whether the code of the doc attribute ends with its text, which is the case for
+	/// doc comments like `/// text`.
+	verbatim: bool
+}
+
+impl DocSpans {
+	/// Return the range in the code that contains `range` of the rustdoc. This is the
+	/// exact range if it is part of a doc comment, or the whole doc attribute otherwise.
+	pub fn find(&self, range: Range<usize>) -> Option<Range<usize>> {
+		let span = self.0.iter().find(|span| span.doc.contains(&range.start))?;
+		if span.verbatim && range.end <= span.doc.end {
+			let end = span.code.end;
+			return Some(
+				end - (span.doc.end - range.start) .. end - (span.doc.end - range.end)
+			);
+		}
+		Some(span.code.clone())
+	}
+}
+
+/// Documentation that was read from doc attributes.
+#[derive(Default)]
+struct Rustdoc {
+	doc: String,
+	spans: DocSpans
+}
+
+impl Rustdoc {
+	/// Append the text of a doc attribute, whose code is at `code`.
+	fn push(&mut self, text: &str, code: Range<usize>, diagnostics: &Diagnostic) {
+		self.doc.push('\n');
+		let start = self.doc.len();
+		self.doc.push_str(text);
+		let verbatim = diagnostics
+			.code()
+			.get(code.clone())
+			.map_or(false, |code| code.ends_with(text));
+		self.spans.0.push(DocSpan {
+			doc: start .. self.doc.len(),
+			code,
+			verbatim
+		});
+	}
+
+	/// Append another documentation.
+	fn append(&mut self, other: Rustdoc) {
+		self.doc.push('\n');
+		let offset = self.doc.len();
+		self.doc.push_str(&other.doc);
+		self.spans
+			.0
+			.extend(other.spans.0.into_iter().map(|span| DocSpan {
+				doc: span.doc.start + offset .. span.doc.end + offset,
+				..span
+			}));
+	}
+
+	/// Remove the spans, which is necessary if the documentation was read from a file
+	/// other than the one that is used for diagnostics.
+	fn without_spans(self) -> Self {
+		Self {
+			doc: self.doc,
+			spans: DocSpans::default()
+		}
+	}
+}
+
+fn read_rustdoc_from_file(
+	file: &syn::File,
+	src_dir: &Path,
+	diagnostics: &mut Diagnostic
+) -> Rustdoc {
+	let mut doc = Rustdoc::default();
+	read_rustdoc_from_attrs(&file.attrs, src_dir, &mut doc, diagnostics);
+	doc
+}
+
+/// Read the documentation of the module at `path` relative to `items`. Only the doc
+/// comments of the module itself are read, not those of the items inside of it.
+/// `src_dir` is the directory of the file that contains `items`, and `mod_dir` the
+/// directory that contains the files of modules declared like `mod foo;`.
+fn read_rustdoc_from_module(
+	items: &[Item],
+	src_dir: &Path,
+	mod_dir: &Path,
+	path: &[&str],
+	diagnostics: &mut Diagnostic
+) -> Option<Rustdoc> {
+	let (name, path) = path.split_first()?;
+	let module = items.iter().find_map(|item| match item {
+		Item::Mod(module) if module.ident == name => Some(module),
+		_ => None
+	})?;
+	let mod_dir = mod_dir.join(name);
+
+	// the attributes of a module contain its outer doc comments, and for inline
+	// modules also the inner doc comments
+	let mut doc = Rustdoc::default();
+	if path.is_empty() {
+		read_rustdoc_from_attrs(&module.attrs, src_dir, &mut doc, diagnostics);
+	}
+	if let Some((_, items)) = &module.content {
+		if path.is_empty() {
+			return Some(doc);
+		}
+		return read_rustdoc_from_module(items, src_dir, &mod_dir, path, diagnostics);
+	}
+
+	let file_path = [mod_dir.with_extension("rs"), mod_dir.join("mod.rs")]
+		.into_iter()
+		.find(|file_path| file_path.exists())?;
+	let code = CrateCode::read_from_disk(&file_path).ok()?;
+	let file = match syn::parse_file(&code.0) {
+		Ok(file) => file,
+		Err(err) => {
+			diagnostics.warn(format_args!(
+				"Unable to parse {}: {err}",
+				file_path.display()
+			));
+			return None;
+		}
+	};
+	// the spans of the module's file cannot be used for diagnostics
+	let src_dir = file_path.parent().unwrap_or(src_dir);
+	if path.is_empty() {
+		read_rustdoc_from_attrs(&file.attrs, src_dir, &mut doc, diagnostics);
+		return Some(doc.without_spans());
+	}
+	read_rustdoc_from_module(&file.items, src_dir, &mod_dir, path, diagnostics)
+		.map(Rustdoc::without_spans)
+}
+
+/// Append the doc comments of all `pub use` statements at the crate root to the rustdoc,
+/// in the order they appear in the source code.
+fn read_reexport_docs_from_file(
+	file: &syn::File,
+	src_dir: &Path,
+	doc: &mut Rustdoc,
+	diagnostics: &mut Diagnostic
+) {
+	for item in &file.items {
+		if let Item::Use(item) = item {
+			if !is_public(&item.vis) {
+				continue;
+			}
+			let mut item_doc = Rustdoc::default();
+			read_rustdoc_from_attrs(&item.attrs, src_dir, &mut item_doc, diagnostics);
+			if !item_doc.doc.is_empty() {
+				doc.append(item_doc);
+			}
+		}
+	}
+}
+
+/// Read the documentation of the first public item that has any. Returns the
+/// documentation, the name of the item and its link type.
+fn read_rustdoc_from_first_item(
+	file: &syn::File,
+	src_dir: &Path,
+	diagnostics: &mut Diagnostic
+) -> Option<(Rustdoc, Ident, LinkType)> {
+	for item in &file.items {
+		let (attrs, vis, ident, link_type) = match item {
+			Item::Const(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Const),
+			Item::Enum(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Enum),
+			Item::Fn(i) => (&i.attrs, &i.vis, &i.sig.ident, LinkType::Function),
+			Item::Mod(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Mod),
+			Item::Static(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Static),
+			Item::Struct(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Struct),
+			Item::Trait(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Trait),
+			Item::Type(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Type),
+			Item::Union(i) => (&i.attrs, &i.vis, &i.ident, LinkType::Union),
+			_ => continue
+		};
+		if !is_public(vis) {
+			continue;
+		}
+		let mut doc = Rustdoc::default();
+		read_rustdoc_from_attrs(attrs, src_dir, &mut doc, diagnostics);
+		if !doc.doc.trim().is_empty() {
+			return Some((doc, ident.clone(), link_type));
+		}
+	}
+	None
+}
+
+fn read_rustdoc_from_attrs(
+	attrs: &[Attribute],
+	src_dir: &Path,
+	doc: &mut Rustdoc,
+	diagnostics: &mut Diagnostic
+) {
+	for attr in attrs {
 		match &attr.meta {
 			Meta::NameValue(nv) if nv.path.is_ident("doc") => {
-				match parse_doc_attr(&nv.value, diagnostics) {
-					Ok(Some(str)) => {
-						doc.push('\n');
-						doc.push_str(&str.value());
-					},
-					Ok(None) => {},
-					Err(err) => {
-						diagnostics.syntax_error(err);
-					}
-				}
+				push_doc_attr(&nv.value, src_dir, doc, diagnostics);
 			},
 
 			Meta::List(l) if l.path.is_ident("cfg_attr") => {
-				parse_cfg_attr(l.tokens.clone(), attr.span(), diagnostics);
+				parse_cfg_attr(l, attr.span(), src_dir, doc, diagnostics);
 			},
 
+			// all other attributes, including list attributes like #[doc(hidden)] or
+			// #![doc(test(attr(deny(warnings))))], don't contain any documentation
 			_ => {}
 		}
 	}
-	doc
+}
+
+/// Warn about indented code blocks in the rustdoc. They are usually the result of prose
+/// that is accidentally indented by four or more spaces.
+fn warn_indented_code_blocks(rustdoc: &Rustdoc, diagnostics: &mut Diagnostic) {
+	let mut in_indented_code_block = false;
+	for (event, range) in Parser::new_ext(&rustdoc.doc, Options::all()).into_offset_iter()
+	{
+		match event {
+			Event::Start(Tag::CodeBlock(CodeBlockKind::Indented)) => {
+				in_indented_code_block = true;
+			},
+			Event::Text(_) if in_indented_code_block => {
+				in_indented_code_block = false;
+				// the label points to the first line of the code block
+				let text = &rustdoc.doc[range.clone()];
+				let start = range.start + (text.len() - text.trim_start().len());
+				let line = rustdoc.doc[start ..].lines().next().unwrap_or_default();
+				let span = rustdoc.spans.find(start .. start + line.trim_end().len());
+				diagnostics.warn_indented_code_block(span);
+			},
+			Event::End(TagEnd::CodeBlock) => in_indented_code_block = false,
+			_ => {}
+		}
+	}
+}
+
+/// Parse the expr of a `#[doc = ...]` attribute and append it to `doc`.
+fn push_doc_attr(
+	expr: &Expr,
+	src_dir: &Path,
+	doc: &mut Rustdoc,
+	diagnostics: &mut Diagnostic
+) {
+	match parse_doc_attr(expr, src_dir, diagnostics) {
+		Ok(Some(str)) => {
+			doc.push(&str, diagnostics.offset_span(expr.span()), diagnostics);
+		},
+		Ok(None) => {},
+		Err(err) => {
+			diagnostics.syntax_error(err);
+		}
+	}
 }
 
 /// Parse the expr of a `#[doc = ...]` attribute. Returns a string if possible, a warning
-/// if it encounters an unexpanded macro or an error if it finds something else.
+/// if it encounters an unexpanded macro or an error if it finds something else. The
+/// `include_str!` macro is read from disk relative to `src_dir`.
 fn parse_doc_attr(
 	expr: &Expr,
+	src_dir: &Path,
 	diagnostics: &mut Diagnostic
-) -> syn::Result<Option<LitStr>> {
+) -> syn::Result<Option<String>> {
+	#[allow(dead_code)]
 	enum LitOrMacro {
 		Lit(LitStr),
 		Macro(Macro)
@@ -448,7 +1076,27 @@ fn parse_doc_attr(
 	match expr {
 		Expr::Lit(ExprLit {
 			lit: Lit::Str(lit), ..
-		}) => Ok(Some(lit.clone())),
+		}) => Ok(Some(lit.value())),
+		Expr::Macro(makro) if makro.mac.path.is_ident("include_str") => {
+			let path = match makro.mac.parse_body::<LitStr>() {
+				Ok(path) => path.value(),
+				Err(_) => {
+					diagnostics.warn_macro_not_expanded(makro.span());
+					return Ok(None);
+				}
+			};
+			match fs::read_to_string(src_dir.join(&path)) {
+				Ok(content) => Ok(Some(content)),
+				Err(err) => {
+					diagnostics.warn_with_label(
+						format_args!("Unable to read {path}: {err}"),
+						makro.span(),
+						"This file will be ignored"
+					);
+					Ok(None)
+				}
+			}
+		},
 		Expr::Macro(makro) => {
 			diagnostics.warn_macro_not_expanded(makro.span());
 			Ok(None)
@@ -460,42 +1108,60 @@ fn parse_doc_attr(
 	}
 }
 
-/// Parse a `#[cfg_attr(..., ...)]` attribute. Returns a warning if it contains a doc
-/// attribute.
-fn parse_cfg_attr(tokens: TokenStream, span: Span, diagnostics: &mut Diagnostic) {
-	struct CfgAttr;
-
-	impl Parse for CfgAttr {
-		fn parse(input: ParseStream) -> syn::Result<Self> {
-			// skip to the 2nd argument
-			while !input.peek(Token![,]) {
-				let _: TokenTree = input.parse()?;
-			}
-			let _: Token![,] = input.parse()?;
-
-			let path: syn::Path = input.parse()?;
-			if !path.is_ident("doc") {
-				return Err(syn::Error::new(
-					path.span(),
-					format!("Expected `doc`, found `{}`", path.into_token_stream())
-				));
-			}
+/// Parse a `#[cfg_attr(..., ...)]` attribute. Doc attributes are read if the predicate
+/// is enabled whenever documentation is built, like `docsrs`. Otherwise, we cannot know
+/// if they apply and return a warning. All other attributes, like
+/// `feature(doc_auto_cfg)`, are ignored.
+fn parse_cfg_attr(
+	list: &MetaList,
+	span: Span,
+	src_dir: &Path,
+	doc: &mut Rustdoc,
+	diagnostics: &mut Diagnostic
+) {
+	let parsed = list.parse_args_with(|input: ParseStream<'_>| {
+		let predicate: Meta = input.parse()?;
+		let _: Token![,] = input.parse()?;
+		let attrs = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+		Ok((predicate, attrs))
+	});
+	let Ok((predicate, attrs)) = parsed else {
+		return;
+	};
 
-			let _: Token![=] = input.parse()?;
-			let _: TokenStream = input.parse()?;
-			Ok(CfgAttr)
+	let mut docs = attrs.into_iter().filter_map(|meta| match meta {
+		Meta::NameValue(nv) if nv.path.is_ident("doc") => Some(nv.value),
+		_ => None
+	});
+	if !is_doc_cfg(&predicate) {
+		if docs.next().is_some() {
+			diagnostics.warn_macro_not_expanded(span);
 		}
+		return;
 	}
-
-	if syn::parse2::<CfgAttr>(tokens).is_ok() {
-		diagnostics.warn_macro_not_expanded(span);
+	for expr in docs {
+		push_doc_attr(&expr, src_dir, doc, diagnostics);
 	}
 }
 
+/// Return true if the `cfg` predicate is always enabled when building documentation.
+fn is_doc_cfg(predicate: &Meta) -> bool {
+	matches!(predicate, Meta::Path(path) if path.is_ident("docsrs") || path.is_ident("doc"))
+}
+
 fn sanitize_crate_name<T: AsRef<str>>(name: T) -> String {
 	name.as_ref().replace('-', "_")
 }
 
+/// Return the name of the package's library, which can be changed using `[lib] name`.
+fn lib_name(pkg: &Package) -> String {
+	pkg.targets
+		.iter()
+		.find(|target| is_lib_target(target))
+		.map(|target| target.name.clone())
+		.unwrap_or_else(|| sanitize_crate_name(&pkg.name))
+}
+
 fn resolve_dependencies(
 	metadata: &Metadata,
 	pkg: &Package,
@@ -510,7 +1176,7 @@ fn resolve_dependencies(
 	// those links might be dead.
 	let version = pkg.version.clone();
 	deps.insert(
-		sanitize_crate_name(&pkg.name),
+		lib_name(pkg),
 		Dependency::new(
 			pkg.name.clone(),
 			[Comparator {
@@ -524,18 +1190,31 @@ fn resolve_dependencies(
 			.collect(),
 			version
 		)
+		.with_lib_name(lib_name(pkg))
+		.with_workspace_member(true)
 	);
 
+	// the packages that the dependency graph resolved for this package. there might be
+	// other versions of the same dependencies that other workspace members depend on
+	let resolved = metadata
+		.resolve
+		.as_ref()
+		.and_then(|resolve| resolve.nodes.iter().find(|node| node.id == pkg.id))
+		.map(|node| &node.dependencies);
+
 	for dep in &pkg.dependencies {
 		let dep_name = sanitize_crate_name(&dep.name);
-		let version = metadata
+		let dep_pkg = metadata
 			.packages
 			.iter()
-			.find(|pkg| pkg.name == dep.name)
-			.map(|pkg| &pkg.version);
+			.filter(|dep_pkg| dep_pkg.name == dep.name)
+			.filter(|dep_pkg| resolved.map_or(true, |ids| ids.contains(&dep_pkg.id)))
+			.find(|dep_pkg| dep.req.matches(&dep_pkg.version))
+			.or_else(|| metadata.packages.iter().find(|pkg| pkg.name == dep.name));
 		let rename = dep.rename.as_ref().unwrap_or(&dep_name);
 
-		if let Some(version) = version {
+		if let Some(dep_pkg) = dep_pkg {
+			let version = &dep_pkg.version;
 			if deps
 				.get(&dep_name)
 				.map(|dep| dep.version < *version)
@@ -544,8 +1223,17 @@ fn resolve_dependencies(
 				deps.insert(
 					rename.to_owned(),
 					Dependency::new(dep_name, dep.req.clone(), version.to_owned())
+						.with_lib_name(lib_name(dep_pkg))
+						.with_workspace_member(
+							metadata.workspace_members.contains(&dep_pkg.id)
+						)
+						.with_kind(dep.kind)
 				);
 			}
+		} else if metadata.resolve.is_none() {
+			// the dependency graph was not resolved, so we don't expect to find any
+			// dependency versions
+			debug!("Unknown version of dependency {}", dep.name);
 		} else {
 			diagnostics
 				.warn(format!("Unable to find version of dependency {}", dep.name));
@@ -558,7 +1246,15 @@ fn resolve_dependencies(
 struct ScopeEditor<'a> {
 	scope: &'a mut Scope,
 	crate_name: &'a str,
-	diagnostics: &'a mut Diagnostic
+	diagnostics: &'a mut Diagnostic,
+	// public items inside of private modules. maps full path to link type.
+	privmod_items: HashMap<String, LinkType>,
+	// whether private items are added to the scope like public ones.
+	include_private: bool,
+	// whether glob uses are only logged instead of causing a warning.
+	allow_glob_use: bool,
+	// the active features, if known. items that require other features are skipped.
+	features: Option<&'a HashSet<String>>
 }
 
 impl<'a> ScopeEditor<'a> {
@@ -570,14 +1266,79 @@ impl<'a> ScopeEditor<'a> {
 		Self {
 			scope,
 			crate_name,
-			diagnostics
+			diagnostics,
+			privmod_items: HashMap::new(),
+			include_private: false,
+			allow_glob_use: false,
+			features: None
 		}
 	}
 
+	/// Return true if an item with this visibility should be added to the scope.
+	fn is_visible(&self, vis: &Visibility) -> bool {
+		self.include_private || is_public(vis)
+	}
+
+	/// Return false if an item with these attributes is disabled by a `#[cfg]` attribute
+	/// that requires features that are not active. Unknown configuration options are
+	/// assumed to be enabled.
+	fn is_enabled(&self, attrs: &[Attribute]) -> bool {
+		let Some(features) = self.features else {
+			return true;
+		};
+		attrs
+			.iter()
+			.filter(|attr| attr.path().is_ident("cfg"))
+			.filter_map(|attr| attr.parse_args::<Meta>().ok())
+			.all(|predicate| eval_cfg(&predicate, features) != Some(false))
+	}
+
 	fn add_privmod(&mut self, ident: &Ident) {
 		self.scope.privmods.insert(ident.to_string());
 	}
 
+	/// Record the public items of an inline module, so that links to them can use the
+	/// correct link type. Items of private modules are only used for re-exports.
+	fn insert_mod_items(&mut self, path: &str, items: &[Item], privmod: bool) {
+		for i in items {
+			if !self.is_enabled(item_attrs(i)) {
+				continue;
+			}
+			let (ident, ty) = match i {
+				Item::Const(i) if self.is_visible(&i.vis) => (&i.ident, LinkType::Const),
+				Item::Enum(i) if self.is_visible(&i.vis) => (&i.ident, LinkType::Enum),
+				Item::Fn(i) if self.is_visible(&i.vis) => {
+					(&i.sig.ident, LinkType::Function)
+				},
+				Item::Mod(i) if self.is_visible(&i.vis) => {
+					if let Some((_, items)) = &i.content {
+						let path = format!("{path}::{}", i.ident);
+						self.insert_mod_items(&path, items, privmod);
+					}
+					(&i.ident, LinkType::Mod)
+				},
+				Item::Static(i) if self.is_visible(&i.vis) => {
+					(&i.ident, LinkType::Static)
+				},
+				Item::Struct(i) if self.is_visible(&i.vis) => {
+					(&i.ident, LinkType::Struct)
+				},
+				Item::Trait(i) if self.is_visible(&i.vis) => (&i.ident, LinkType::Trait),
+				Item::TraitAlias(i) if self.is_visible(&i.vis) => {
+					(&i.ident, LinkType::TraitAlias)
+				},
+				Item::Type(i) if self.is_visible(&i.vis) => (&i.ident, LinkType::Type),
+				Item::Union(i) if self.is_visible(&i.vis) => (&i.ident, LinkType::Union),
+				_ => continue
+			};
+			let items = match privmod {
+				true => &mut self.privmod_items,
+				false => &mut self.scope.items
+			};
+			items.insert(format!("{path}::{ident}"), ty);
+		}
+	}
+
 	fn insert(&mut self, ident: &Ident, ty: LinkType) {
 		let path = format!("::{}::{ident}", self.crate_name);
 		self.scope.insert(ident.to_string(), ty, path);
@@ -592,10 +1353,17 @@ impl<'a> ScopeEditor<'a> {
 	}
 
 	fn insert_macro(&mut self, ident: &Ident) {
+		// links to macros with a raw identifier as their name might omit the `r#` prefix
+		let name = ident.unraw();
 		let path = format!("::{}::{ident}", self.crate_name);
-		self.scope.insert(ident.to_string(), LinkType::Macro, &path);
+		self.scope.insert(name.to_string(), LinkType::Macro, &path);
 		self.scope
-			.insert(format!("{ident}!"), LinkType::Macro, path);
+			.insert(format!("{name}!"), LinkType::Macro, &path);
+		if *ident != name {
+			self.scope.insert(ident.to_string(), LinkType::Macro, &path);
+			self.scope
+				.insert(format!("{ident}!"), LinkType::Macro, path);
+		}
 	}
 
 	fn insert_use_tree(&mut self, vis: &Visibility, tree: &UseTree) {
@@ -619,6 +1387,9 @@ impl<'a> ScopeEditor<'a> {
 			UseTree::Rename(name) => {
 				self.insert_use_item(vis, &prefix, &name.rename, &name.ident);
 			},
+			UseTree::Glob(_) if self.allow_glob_use => {
+				debug!("Skipping glob use {prefix}*");
+			},
 			UseTree::Glob(glob) => {
 				self.diagnostics.warn_with_label(
 					"Glob use statements can lead to incomplete link generation.",
@@ -652,6 +1423,130 @@ impl<'a> ScopeEditor<'a> {
 	}
 }
 
+/// Return the attributes of an item.
+fn item_attrs(item: &Item) -> &[Attribute] {
+	match item {
+		Item::Const(i) => &i.attrs,
+		Item::Enum(i) => &i.attrs,
+		Item::ExternCrate(i) => &i.attrs,
+		Item::Fn(i) => &i.attrs,
+		Item::ForeignMod(i) => &i.attrs,
+		Item::Impl(i) => &i.attrs,
+		Item::Macro(i) => &i.attrs,
+		Item::Mod(i) => &i.attrs,
+		Item::Static(i) => &i.attrs,
+		Item::Struct(i) => &i.attrs,
+		Item::Trait(i) => &i.attrs,
+		Item::TraitAlias(i) => &i.attrs,
+		Item::Type(i) => &i.attrs,
+		Item::Union(i) => &i.attrs,
+		Item::Use(i) => &i.attrs,
+		_ => &[]
+	}
+}
+
+/// Evaluate a `#[cfg]` predicate with the active features. Returns `None` if the result
+/// depends on anything other than features.
+fn eval_cfg(predicate: &Meta, features: &HashSet<String>) -> Option<bool> {
+	let list = |list: &MetaList| {
+		list.parse_args_with(Punctuated::<Meta, Token![,]>::parse_terminated)
+			.ok()
+			.map(|predicates| {
+				predicates
+					.iter()
+					.map(|predicate| eval_cfg(predicate, features))
+					.collect::<Vec<_>>()
+			})
+	};
+	match predicate {
+		Meta::NameValue(nv) if nv.path.is_ident("feature") => match &nv.value {
+			Expr::Lit(ExprLit {
+				lit: Lit::Str(feature),
+				..
+			}) => Some(features.contains(&feature.value())),
+			_ => None
+		},
+		Meta::List(l) if l.path.is_ident("all") => {
+			let results = list(l)?;
+			if results.contains(&Some(false)) {
+				Some(false)
+			} else if results.iter().all(Option::is_some) {
+				Some(true)
+			} else {
+				None
+			}
+		},
+		Meta::List(l) if l.path.is_ident("any") => {
+			let results = list(l)?;
+			if results.contains(&Some(true)) {
+				Some(true)
+			} else if results.iter().all(Option::is_some) {
+				Some(false)
+			} else {
+				None
+			}
+		},
+		Meta::List(l) if l.path.is_ident("not") => {
+			let results = list(l)?;
+			match results.as_slice() {
+				[result] => result.map(|result| !result),
+				_ => None
+			}
+		},
+		_ => None
+	}
+}
+
+/// Return the features that are active with the feature options, or `None` if no
+/// feature options were given on the command line. The features from the package metadata
+/// are added to those from the command line, but don't exclude any items on their own.
+fn active_features(
+	pkg: &Package,
+	options: &InputOptions,
+	package_metadata: &PackageMetadata
+) -> Option<HashSet<String>> {
+	if options.features.is_none() && !options.all_features && !options.no_default_features
+	{
+		return None;
+	}
+	let features = if options.no_default_features {
+		options.features.clone()
+	} else {
+		merge_features(options.features.as_deref(), &package_metadata.features)
+	};
+
+	let mut queue: VecDeque<String> = if options.all_features {
+		pkg.features.keys().cloned().collect()
+	} else {
+		features
+			.iter()
+			.flat_map(|features| features.split([' ', ',']))
+			.filter(|feature| !feature.is_empty() && !feature.contains('/'))
+			.map(String::from)
+			.chain((!options.no_default_features).then(|| "default".into()))
+			.collect()
+	};
+	let mut features = HashSet::new();
+	while let Some(feature) = queue.pop_front() {
+		if features.contains(&feature) {
+			continue;
+		}
+		for enables in pkg.features.get(&feature).into_iter().flatten() {
+			// `dep:name` and `name?/feature` do not enable the feature `name`
+			if enables.starts_with("dep:") {
+				continue;
+			}
+			match enables.split_once('/') {
+				Some((dep, _)) if dep.ends_with('?') => {},
+				Some((dep, _)) => queue.push_back(dep.to_owned()),
+				None => queue.push_back(enables.clone())
+			}
+		}
+		features.insert(feature);
+	}
+	Some(features)
+}
+
 fn is_public(vis: &Visibility) -> bool {
 	matches!(vis, Visibility::Public(_))
 }
@@ -665,20 +1560,37 @@ fn is_exported(mac: &ItemMacro) -> bool {
 fn read_scope_from_file(
 	pkg: &Package,
 	file: &syn::File,
+	proc_macro: bool,
+	options: &InputOptions,
+	package_metadata: &PackageMetadata,
 	diagnostics: &mut Diagnostic
 ) -> Scope {
-	let crate_name = sanitize_crate_name(&pkg.name);
-	let mut scope = Scope::prelude(pkg.edition);
+	let crate_name = lib_name(pkg);
+	let mut scope = Scope::prelude(options.edition.unwrap_or(pkg.edition));
+	if proc_macro {
+		scope.add_proc_macro_prelude();
+	}
+	let features = active_features(pkg, options, package_metadata);
 	let mut editor = ScopeEditor::new(&mut scope, &crate_name, diagnostics);
+	editor.include_private = options.include_private;
+	editor.allow_glob_use = options.allow_glob_use || package_metadata.allow_glob_use;
+	editor.features = features.as_ref();
 
 	for i in &file.items {
+		if !editor.is_enabled(item_attrs(i)) {
+			continue;
+		}
 		match i {
-			Item::Const(i) if is_public(&i.vis) => {
+			Item::Const(i) if editor.is_visible(&i.vis) => {
 				editor.insert(&i.ident, LinkType::Const)
 			},
-			Item::Enum(i) if is_public(&i.vis) => editor.insert(&i.ident, LinkType::Enum),
+			Item::Enum(i) if editor.is_visible(&i.vis) => {
+				editor.insert(&i.ident, LinkType::Enum)
+			},
 			Item::ExternCrate(i)
-				if is_public(&i.vis) && i.ident != "self" && i.rename.is_some() =>
+				if editor.is_visible(&i.vis)
+					&& i.ident != "self"
+					&& i.rename.is_some() =>
 			{
 				editor.scope.insert(
 					i.rename.as_ref().unwrap().1.to_string(),
@@ -686,26 +1598,42 @@ fn read_scope_from_file(
 					format!("::{}", i.ident)
 				);
 			},
-			Item::Fn(i) if is_public(&i.vis) => editor.insert_fun(&i.sig.ident),
-			Item::Macro(i) if is_exported(i) && i.ident.is_some() => {
-				editor.insert_macro(i.ident.as_ref().unwrap())
+			Item::Fn(i) if editor.is_visible(&i.vis) => editor.insert_fun(&i.sig.ident),
+			Item::Macro(i) if is_exported(i) => match &i.ident {
+				Some(ident) => editor.insert_macro(ident),
+				None => debug!(
+					"Skipping exported macro without a name: {}",
+					i.mac.path.to_token_stream()
+				)
+			},
+			Item::Mod(i) => {
+				let privmod = !editor.is_visible(&i.vis);
+				if privmod {
+					editor.add_privmod(&i.ident);
+				} else {
+					editor.insert(&i.ident, LinkType::Mod);
+				}
+				if let Some((_, items)) = &i.content {
+					let path = format!("::{crate_name}::{}", i.ident);
+					editor.insert_mod_items(&path, items, privmod);
+				}
 			},
-			Item::Mod(i) if is_public(&i.vis) => editor.insert(&i.ident, LinkType::Mod),
-			Item::Mod(i) => editor.add_privmod(&i.ident),
-			Item::Static(i) if is_public(&i.vis) => {
+			Item::Static(i) if editor.is_visible(&i.vis) => {
 				editor.insert(&i.ident, LinkType::Static)
 			},
-			Item::Struct(i) if is_public(&i.vis) => {
+			Item::Struct(i) if editor.is_visible(&i.vis) => {
 				editor.insert(&i.ident, LinkType::Struct)
 			},
-			Item::Trait(i) if is_public(&i.vis) => {
+			Item::Trait(i) if editor.is_visible(&i.vis) => {
 				editor.insert(&i.ident, LinkType::Trait)
 			},
-			Item::TraitAlias(i) if is_public(&i.vis) => {
+			Item::TraitAlias(i) if editor.is_visible(&i.vis) => {
 				editor.insert(&i.ident, LinkType::TraitAlias)
 			},
-			Item::Type(i) if is_public(&i.vis) => editor.insert(&i.ident, LinkType::Type),
-			Item::Union(i) if is_public(&i.vis) => {
+			Item::Type(i) if editor.is_visible(&i.vis) => {
+				editor.insert(&i.ident, LinkType::Type)
+			},
+			Item::Union(i) if editor.is_visible(&i.vis) => {
 				editor.insert(&i.ident, LinkType::Union)
 			},
 			Item::Use(i) if !is_prelude_import(i) => {
@@ -715,27 +1643,69 @@ fn read_scope_from_file(
 		};
 	}
 
-	// remove privmod imports from scope
-	for values in &mut scope.scope.values_mut() {
+	let privmod_items = editor.privmod_items;
+
+	// remove privmod imports from scope. the items are re-exported (e.g. `pub use
+	// self::privmod::Item;`) and inlined by rustdoc, so we use their link type for the
+	// re-export if it is known.
+	let crate_prefix = format!("::{crate_name}::");
+	for (name, values) in &mut scope.scope {
+		let mut reexport_link_type = None;
 		let mut i = 0;
 		while i < values.len() {
 			if values[i].0 == LinkType::Use {
 				let path = &values[i].1;
-				if (!path.starts_with("::")
-					|| path.starts_with(&format!("::{crate_name}::")))
-					&& Some(path.split("::").collect::<Vec<_>>())
-						.map(|segments| {
-							segments.len() > 1 && scope.privmods.contains(segments[0])
-						})
-						.unwrap()
-				{
-					values.remove(i);
-					continue;
+				let local_path = path
+					.strip_prefix(crate_prefix.as_str())
+					.or_else(|| path.strip_prefix("crate::"))
+					.or_else(|| path.strip_prefix("self::"))
+					.or_else(|| (!path.starts_with("::")).then_some(path.as_str()));
+				if let Some(local_path) = local_path {
+					let segments = local_path.split("::").collect::<Vec<_>>();
+					if segments.len() > 1 && scope.privmods.contains(segments[0]) {
+						reexport_link_type = privmod_items
+							.get(&format!("{crate_prefix}{local_path}"))
+							.copied();
+						values.remove(i);
+						continue;
+					}
+
+					// a re-exported private module, like `pub use self::inner as outer;`,
+					// is inlined as a public module, so its items are available under
+					// the new name
+					if segments.len() == 1 && scope.privmods.contains(segments[0]) {
+						let privmod_prefix = format!("{crate_prefix}{local_path}::");
+						let mod_prefix = format!("{crate_prefix}{name}::");
+						for (path, link_type) in &privmod_items {
+							if let Some(item_path) = path.strip_prefix(&privmod_prefix) {
+								scope.items.insert(
+									format!("{mod_prefix}{item_path}"),
+									*link_type
+								);
+							}
+						}
+						reexport_link_type = Some(LinkType::Mod);
+						values.remove(i);
+						continue;
+					}
 				}
 			}
 
 			i += 1;
 		}
+
+		if let Some(link_type) = reexport_link_type {
+			for value in values.iter_mut() {
+				if value.0 == LinkType::PubUse {
+					value.0 = link_type;
+				}
+			}
+		}
+	}
+
+	// remove names that the user asked us to ignore
+	for name in &package_metadata.ignore_links {
+		scope.scope.remove(name);
 	}
 
 	scope
@@ -754,3 +1724,33 @@ fn is_prelude_import(item_use: &ItemUse) -> bool {
 		_ => false
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::merge_features;
+
+	#[test]
+	fn merge_features_union() {
+		let metadata = vec!["b".to_owned(), "c".to_owned()];
+		assert_eq!(
+			merge_features(Some("a b,c"), &metadata).as_deref(),
+			Some("a,b,c")
+		);
+	}
+
+	#[test]
+	fn merge_features_cli_only() {
+		assert_eq!(merge_features(Some("a, b"), &[]).as_deref(), Some("a,b"));
+	}
+
+	#[test]
+	fn merge_features_metadata_only() {
+		let metadata = vec!["a".to_owned()];
+		assert_eq!(merge_features(None, &metadata).as_deref(), Some("a"));
+	}
+
+	#[test]
+	fn merge_features_none() {
+		assert_eq!(merge_features(None, &[]), None);
+	}
+}
@@ -2,56 +2,170 @@
 //! ADHERE TO SEMVER. DON'T EVEN USE AT YOUR OWN RISK. DON'T USE IT
 //! AT ALL.**
 
-use cargo_metadata::{CargoOpt, MetadataCommand, Target};
-use log::{debug, info};
+use cargo_metadata::{CargoOpt, Edition, Metadata, MetadataCommand, Target};
+use log::{debug, info, warn};
 use semver::Version;
-use std::{borrow::Cow, collections::HashMap, env, fmt::Display, fs, path::PathBuf};
+use std::{
+	borrow::Cow,
+	collections::HashMap,
+	env,
+	fmt::Display,
+	fs, io,
+	path::{Path, PathBuf},
+	str,
+	time::Duration
+};
 
 #[doc(hidden)]
 pub mod depinfo;
 #[doc(hidden)]
 pub mod diagnostic;
+mod fetch;
 #[doc(hidden)]
 pub mod input;
 #[doc(hidden)]
 pub mod links;
 #[doc(hidden)]
+pub mod manifest;
+#[doc(hidden)]
 pub mod output;
 #[doc(hidden)]
 pub mod preproc;
+mod render;
 #[doc(hidden)]
 pub mod verify;
 
 use crate::input::Scope;
 use diagnostic::Diagnostic;
-use input::{CrateCode, InputFile, TargetType};
+use input::{CrateCode, InputFile, PackageMetadata, TargetType};
+
+#[doc(hidden)]
+/// Options for reading the input, see [`read_input`].
+#[derive(Clone, Debug, Default)]
+pub struct InputOptions {
+	/// The manifest path that will be passed to `cargo metadata`.
+	pub manifest_path: Option<PathBuf>,
+	/// The package to read.
+	pub package: Option<String>,
+	/// Prefer binary targets over library targets.
+	pub prefer_bin: bool,
+	/// Read the rustdoc from the binary target with this name.
+	pub bin_name: Option<String>,
+	/// Use the rust compiler to expand macros. This will only work on a nightly compiler.
+	pub expand_macros: bool,
+	/// The template file. This doesn't have to exist, a default will be used if it does
+	/// not exist.
+	pub template: PathBuf,
+	/// The features to activate when expanding macros.
+	pub features: Option<String>,
+	/// Do not activate the default feature when expanding macros.
+	pub no_default_features: bool,
+	/// Activate all features when expanding macros.
+	pub all_features: bool,
+	/// The target directory to use when expanding macros.
+	pub target_dir: Option<PathBuf>,
+	/// Abort expanding macros if cargo does not finish within this duration.
+	pub expand_timeout: Option<Duration>,
+	/// The cargo profile to use when expanding macros.
+	pub profile: Option<String>,
+	/// Read the `Cargo.toml` directly instead of running `cargo metadata`.
+	pub no_run_cargo: bool,
+	/// Append the doc comments of `pub use` statements at the crate root to the rustdoc.
+	pub include_reexport_docs: bool,
+	/// Use the documentation of the first public item if the crate has none.
+	pub fallback_to_item: bool,
+	/// Add private items to the scope so that links to them can be resolved.
+	pub include_private: bool,
+	/// Do not warn about glob uses, even though their items are not used for links.
+	pub allow_glob_use: bool,
+	/// Run the doc tests of the library and fail if any of them fails.
+	pub verify_doctests: bool,
+	/// Use the prelude of this edition instead of the edition of the package.
+	pub edition: Option<Edition>,
+	/// Read the library, binary or example target with this name.
+	pub target_name: Option<String>,
+	/// Read the target with this crate type.
+	pub crate_type: Option<String>,
+	/// Read the documentation of this module (e.g. `foo::bar`) instead of the crate.
+	pub doc_from: Option<String>
+}
+
+/// Read the template from `path`, or return the built-in template if the file does not
+/// exist. If `path` is a http(s) url, the template is downloaded instead, falling back to
+/// the built-in template if that fails.
+pub fn read_template(path: &Path) -> io::Result<Cow<'static, str>> {
+	if let Some(url) = path.to_str().filter(|path| fetch::is_url(path)) {
+		return Ok(match fetch::fetch_template(url) {
+			Some(template) => template.into(),
+			None => {
+				warn!("Using the built-in template instead");
+				include_str!("README.j2").into()
+			}
+		});
+	}
+	if path.exists() {
+		Ok(fs::read_to_string(path)?.into())
+	} else {
+		Ok(include_str!("README.j2").into())
+	}
+}
+
+/// Run `cargo metadata` like [`MetadataCommand::exec`], but also return the warnings
+/// that cargo printed.
+fn exec_metadata(
+	cmd: &MetadataCommand
+) -> cargo_metadata::Result<(Metadata, Vec<String>)> {
+	let output = cmd.cargo_command().output()?;
+	let stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+	if !output.status.success() {
+		return Err(cargo_metadata::Error::CargoMetadata { stderr });
+	}
+	let stdout = str::from_utf8(&output.stdout)?
+		.lines()
+		.find(|line| line.starts_with('{'))
+		.ok_or(cargo_metadata::Error::NoJson)?;
+	let mut warnings: Vec<String> = Vec::new();
+	let mut in_warning = false;
+	for line in stderr.lines() {
+		if let Some(warning) = line.strip_prefix("warning: ") {
+			warnings.push(warning.into());
+			in_warning = true;
+		} else if in_warning
+			&& (line.starts_with(char::is_whitespace) || line.starts_with("note: "))
+		{
+			// continuation lines like `  = note: ...` that belong to the last warning
+			let warning = warnings.last_mut().unwrap();
+			warning.push('\n');
+			warning.push_str(line);
+		} else {
+			in_warning = false;
+		}
+	}
+	Ok((MetadataCommand::parse(stdout)?, warnings))
+}
 
 #[doc(hidden)]
-#[allow(clippy::too_many_arguments)] // TODO
 /// Read input. The manifest path options, if present, will be passed to
 /// `cargo metadata`. If you set expand_macros to true, the input will be passed to the
 /// rust compiler to expand macros. This will only work on a nightly compiler. The
 /// template doesn't have to exist, a default will be used if it does not exist.
-pub fn read_input(
-	manifest_path: Option<PathBuf>,
-	package: Option<String>,
-	prefer_bin: bool,
-	expand_macros: bool,
-	template: PathBuf,
-	features: Option<String>,
-	no_default_features: bool,
-	all_features: bool
-) -> (InputFile, Cow<'static, str>, Diagnostic) {
+pub fn read_input(options: InputOptions) -> (InputFile, Cow<'static, str>, Diagnostic) {
 	/// Create a fake input when reading the input failed before we had any code.
 	fn fail<T: Display>(msg: T) -> (InputFile, Cow<'static, str>, Diagnostic) {
 		let input = InputFile {
 			crate_name: "N/A".into(),
+			lib_name: "N/A".into(),
 			crate_version: Version::new(0, 0, 0),
 			target_type: TargetType::Lib,
 			repository: None,
 			license: None,
+			authors: Vec::new(),
 			rust_version: None,
+			keywords: Vec::new(),
+			categories: Vec::new(),
+			metadata: Default::default(),
 			rustdoc: String::new(),
+			rustdoc_spans: Default::default(),
 			dependencies: HashMap::new(),
 			scope: Scope::empty()
 		};
@@ -100,20 +214,31 @@ pub fn read_input(
 	}
 
 	// get the cargo manifest path
-	let manifest_path = match manifest_path {
+	let manifest_path = match &options.manifest_path {
 		Some(path) if path.is_relative() => Some(env::current_dir().unwrap().join(path)),
-		Some(path) => Some(path),
+		Some(path) => Some(path.clone()),
 		None => None
 	};
 
 	// parse the cargo metadata
-	let mut cmd = MetadataCommand::new();
-	cmd.features(CargoOpt::AllFeatures);
-	if let Some(path) = &manifest_path {
-		cmd.manifest_path(path);
-	}
-	let metadata = unwrap!(cmd.exec(), "Failed to get cargo metadata");
-	let pkg = match package.as_deref() {
+	let mut metadata_warnings = Vec::new();
+	let metadata = if options.no_run_cargo {
+		unwrap!(
+			manifest::read_metadata(manifest_path.as_deref()),
+			"Failed to read cargo manifest"
+		)
+	} else {
+		let mut cmd = MetadataCommand::new();
+		cmd.features(CargoOpt::AllFeatures);
+		if let Some(path) = &manifest_path {
+			cmd.manifest_path(path);
+		}
+		let (metadata, warnings) =
+			unwrap!(exec_metadata(&cmd), "Failed to get cargo metadata");
+		metadata_warnings = warnings;
+		metadata
+	};
+	let pkg = match options.package.as_deref() {
 		Some(package) => unwrap!(
 			metadata.packages.iter().find(|pkg| pkg.name == package),
 			"Cannot find requested package"
@@ -127,13 +252,60 @@ Help: You can use --manifest-path and/or -p to specify the package to use."#
 		)
 	};
 
+	// features from the package metadata are activated together with the default features
+	// whenever we run cargo
+	let mut cargo_options = options.clone();
+	if !options.no_default_features {
+		cargo_options.features = input::merge_features(
+			options.features.as_deref(),
+			&PackageMetadata::read_features(pkg)
+		);
+	}
+
 	// find the target whose rustdoc comment we'll use.
 	// this uses a library target if exists, otherwise a binary target with the same name as the
 	// package, or otherwise the first binary target
-	let is_lib = |target: &&Target| target.is_lib();
+	let is_lib = |target: &&Target| input::is_lib_target(target);
 	let is_default_bin =
 		|target: &&Target| target.is_bin() && target.name == pkg.name.as_str();
-	let target_and_type = if prefer_bin {
+	let target_and_type = if let Some(target_name) = options.target_name.as_deref() {
+		let target = unwrap!(
+			pkg.targets.iter().find(|target| {
+				target.name == target_name
+					&& (input::is_lib_target(target)
+						|| target.is_bin() || target.is_example())
+			}),
+			"Cannot find requested target"
+		);
+		Some(if input::is_lib_target(target) {
+			(target, TargetType::Lib)
+		} else {
+			(target, TargetType::Bin)
+		})
+	} else if let Some(crate_type) = options.crate_type.as_deref() {
+		let target = unwrap!(
+			pkg.targets.iter().find(|target| {
+				(input::is_lib_target(target) || target.is_bin())
+					&& target.crate_types.iter().any(|ty| ty == crate_type)
+			}),
+			"Cannot find a target with the requested crate type"
+		);
+		Some(if input::is_lib_target(target) {
+			(target, TargetType::Lib)
+		} else {
+			(target, TargetType::Bin)
+		})
+	} else if let Some(bin_name) = options.bin_name.as_deref() {
+		Some((
+			unwrap!(
+				pkg.targets
+					.iter()
+					.find(|target| target.is_bin() && target.name == bin_name),
+				"Cannot find requested binary target"
+			),
+			TargetType::Bin
+		))
+	} else if options.prefer_bin {
 		pkg.targets
 			.iter()
 			.find(is_default_bin)
@@ -167,11 +339,7 @@ Help: You can use --manifest-path and/or -p to specify the package to use."#
 	);
 
 	// resolve the template
-	let template: Cow<'static, str> = if template.exists() {
-		unwrap!(fs::read_to_string(template), "Failed to read template").into()
-	} else {
-		include_str!("README.j2").into()
-	};
+	let template = unwrap!(read_template(&options.template), "Failed to read template");
 
 	// read crate code
 	let file = target.src_path.as_std_path();
@@ -180,28 +348,41 @@ Help: You can use --manifest-path and/or -p to specify the package to use."#
 		.expect("File has no filename")
 		.to_string_lossy()
 		.into_owned();
-	let code = if expand_macros {
+	let code = if options.expand_macros {
 		unwrap!(
-			CrateCode::read_expansion(
-				manifest_path.as_ref(),
-				package,
-				target,
-				features,
-				no_default_features,
-				all_features
-			),
+			CrateCode::read_expansion(manifest_path.as_ref(), target, &cargo_options),
 			"Failed to read crate code"
 		)
 	} else {
 		unwrap!(CrateCode::read_from_disk(file), "Failed to read crate code")
 	};
 	let mut diagnostics = Diagnostic::new(filename, code.0.clone());
+	for warning in metadata_warnings {
+		diagnostics.warn(format_args!("cargo metadata: {warning}"));
+	}
 
 	// process the target
 	info!("Reading {}", file.display());
-	let input_file =
-		input::read_code(&metadata, pkg, code, target_type, &mut diagnostics);
+	let input_file = input::read_code(
+		&metadata,
+		pkg,
+		target,
+		code,
+		target_type,
+		&options,
+		&mut diagnostics
+	);
 	debug!("Processing {input_file:#?}");
 
+	// make sure the examples in the documentation compile
+	if options.verify_doctests && !diagnostics.is_fail() {
+		input::verify_doctests(
+			manifest_path.as_ref(),
+			target,
+			&cargo_options,
+			&mut diagnostics
+		);
+	}
+
 	(input_file, template, diagnostics)
 }
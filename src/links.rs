@@ -1,28 +1,98 @@
 use crate::{
 	depinfo::DependencyInfo,
-	input::{Dependency, InputFile, LinkType}
+	diagnostic::Diagnostic,
+	input::{InputFile, LinkType}
 };
 use either::Either;
-use syn::Path;
+use quote::ToTokens as _;
+use syn::{ext::IdentExt as _, Path};
+
+/// The crates of the standard library, which are never dependencies.
+pub(crate) const STD_CRATES: &[&str] = &["alloc", "core", "proc_macro", "std", "test"];
 
 pub struct Links {
-	pub deps: DependencyInfo
+	pub deps: DependencyInfo,
+
+	/// Link to the docs of workspace members relative to this base instead of docs.rs.
+	pub local_docs_base: Option<String>,
+
+	/// The base url for links to crates, instead of `https://crates.io`.
+	pub crates_io_base_url: Option<String>,
+
+	/// The base url for links to the docs of crates, instead of `https://docs.rs`.
+	pub docs_rs_base_url: Option<String>,
+
+	/// The base url for links to the standard library, instead of
+	/// `https://doc.rust-lang.org/stable`.
+	pub std_docs_base_url: Option<String>,
+
+	/// Link to the stable standard library even if the crate has a `rust-version`.
+	pub std_docs_stable: bool,
+
+	/// Do not guess that unknown items of a type are methods or enum variants.
+	pub no_method_anchors: bool
+}
+
+/// Return the page of an item, relative to the module that contains it.
+fn item_page(link_type: LinkType, name: &str) -> Option<String> {
+	Some(match link_type {
+		LinkType::Const => format!("constant.{name}.html"),
+		LinkType::Enum => format!("enum.{name}.html"),
+		LinkType::Macro => format!("macro.{name}.html"),
+		LinkType::Mod => format!("{name}/index.html"),
+		LinkType::Primitive => format!("primitive.{name}.html"),
+		LinkType::Static => format!("static.{name}.html"),
+		LinkType::Struct => format!("struct.{name}.html"),
+		LinkType::Trait => format!("trait.{name}.html"),
+		LinkType::Type => format!("type.{name}.html"),
+		_ => return None
+	})
+}
+
+/// Guess the kind of an unknown item of a type from its name, and return the fragment of
+/// the item on the page of the type. Methods are snake case, enum variants and associated
+/// types of traits camel case.
+fn item_fragment(parent_link_type: LinkType, name: &str) -> Option<String> {
+	let uppercase = name.starts_with(|ch: char| ch.is_uppercase());
+	match parent_link_type {
+		LinkType::Enum if uppercase => Some(format!("variant.{name}")),
+		LinkType::Trait if uppercase => Some(format!("associatedtype.{name}")),
+		LinkType::Enum
+		| LinkType::Primitive
+		| LinkType::Struct
+		| LinkType::Trait
+		| LinkType::Type
+			if !uppercase =>
+		{
+			Some(format!("method.{name}"))
+		},
+		_ => None
+	}
 }
 
 impl Links {
 	pub fn new(template: &str, rustdoc: &str) -> Self {
 		Self {
-			deps: DependencyInfo::new(template, rustdoc)
+			deps: DependencyInfo::new(template, rustdoc),
+			local_docs_base: None,
+			crates_io_base_url: None,
+			docs_rs_base_url: None,
+			std_docs_base_url: None,
+			std_docs_stable: false,
+			no_method_anchors: false
 		}
 	}
 
 	/// Build a link for an already fully resolved path. This method assumes that the
-	/// first part of the path is the crate the path comes from.
+	/// first part of the path is the crate the path comes from. If the type of the item
+	/// is unknown, but the type of its parent is, the link points to the parent's page.
 	pub fn build_link(
 		&mut self,
 		path: &Path,
 		link_type: Option<LinkType>,
-		input: &InputFile
+		parent_link_type: Option<LinkType>,
+		input: &InputFile,
+		diagnostics: &mut Diagnostic
 	) -> String {
 		let mut first = path
 			.segments
@@ -33,7 +103,8 @@ impl Links {
 			.segments
 			.iter()
 			.skip(1)
-			.filter_map(|segment| match segment.ident.to_string() {
+			// rustdoc uses the names of raw identifiers without the `r#` prefix
+			.filter_map(|segment| match segment.ident.unraw().to_string() {
 				ident if ident == "crate" => None,
 				ident => Some(ident)
 			})
@@ -41,34 +112,72 @@ impl Links {
 
 		// resolve crate:: and self:: links
 		if (first == "crate" || first == "self") && path.leading_colon.is_none() {
-			first = input.crate_name.replace('-', "_");
+			first = input.lib_name.clone();
 		}
 
 		// get base url based on first segment
 		let base_url = match first.as_str() {
-			"alloc" | "core" | "proc_macro" | "std" | "test" => {
-				format!("https://doc.rust-lang.org/stable/{first}")
+			first if STD_CRATES.contains(&first) => {
+				let base_url = match (&self.std_docs_base_url, &input.rust_version) {
+					(Some(base_url), _) => base_url.trim_end_matches('/').to_owned(),
+					// the docs of old releases are only available for full versions
+					(None, Some(version)) if !self.std_docs_stable => format!(
+						"https://doc.rust-lang.org/{}.{}.{}",
+						version.major, version.minor, version.patch
+					),
+					(None, _) => "https://doc.rust-lang.org/stable".to_owned()
+				};
+				format!("{base_url}/{first}")
 			},
 			_ => {
-				let (crate_name, crate_ver) = input
-					.dependencies
-					.get(&first)
-					.map(Dependency::as_tuple)
-					.unwrap_or((&first, None));
-				let lib_name = crate_name.replace('-', "_");
+				let dep = input.dependencies.get(&first);
+				let (crate_name, crate_ver, lib_name) = match dep {
+					Some(dep) => {
+						(&dep.crate_name, Some(&dep.version), dep.lib_name.clone())
+					},
+					None => {
+						// links to unknown items are reported as broken links by the caller
+						if link_type.is_some() {
+							diagnostics.warn(format_args!(
+								"Link to `{}` might be wrong as `{first}` is not a dependency",
+								path.to_token_stream().to_string().replace(' ', "")
+							));
+						}
+						(&first, None, first.replace('-', "_"))
+					}
+				};
 				self.deps.add_dependency(
 					crate_name.to_owned(),
 					crate_ver.cloned(),
 					lib_name.clone()
 				);
-				if segments.is_empty() {
+				let local_docs_base = self
+					.local_docs_base
+					.as_deref()
+					.filter(|_| dep.map_or(false, |dep| dep.workspace_member));
+				if let Some(local_docs_base) = local_docs_base {
+					let base_url =
+						format!("{}/{lib_name}", local_docs_base.trim_end_matches('/'));
+					if segments.is_empty() {
+						return format!("{base_url}/index.html");
+					}
+					base_url
+				} else if segments.is_empty() {
 					format!(
-						"https://crates.io/crates/{crate_name}{}",
+						"{}/crates/{crate_name}{}",
+						self.crates_io_base_url
+							.as_deref()
+							.unwrap_or("https://crates.io")
+							.trim_end_matches('/'),
 						crate_ver.map(|ver| format!("/{ver}")).unwrap_or_default()
 					)
 				} else {
 					format!(
-						"https://docs.rs/{crate_name}/{}/{lib_name}",
+						"{}/{crate_name}/{}/{lib_name}",
+						self.docs_rs_base_url
+							.as_deref()
+							.unwrap_or("https://docs.rs")
+							.trim_end_matches('/'),
 						crate_ver
 							.map(Either::Left)
 							.unwrap_or(Either::Right("latest"))
@@ -88,76 +197,221 @@ impl Links {
 		if !segments_uri.is_empty() {
 			segments_uri += "/";
 		}
-		match link_type {
-			Some(LinkType::Const) => {
-				format!("{base_url}/{segments_uri}constant.{last}.html")
-			},
-			Some(LinkType::Enum) => {
-				format!("{base_url}/{segments_uri}enum.{last}.html")
-			},
-			Some(LinkType::Macro) => {
-				format!("{base_url}/{segments_uri}macro.{last}.html")
-			},
-			Some(LinkType::Mod) => {
-				format!("{base_url}/{segments_uri}{last}/index.html")
-			},
-			Some(LinkType::Primitive) => {
-				format!("{base_url}/{segments_uri}primitive.{last}.html")
-			},
-			Some(LinkType::Static) => {
-				format!("{base_url}/{segments_uri}static.{last}.html")
-			},
-			Some(LinkType::Struct) => {
-				format!("{base_url}/{segments_uri}struct.{last}.html")
-			},
-			Some(LinkType::Trait) => {
-				format!("{base_url}/{segments_uri}trait.{last}.html")
-			},
-			Some(LinkType::Type) => {
-				format!("{base_url}/{segments_uri}type.{last}.html")
-			},
+		if let Some(page) = link_type.and_then(|link_type| item_page(link_type, &last)) {
+			return format!("{base_url}/{segments_uri}{page}");
+		}
 
-			_ => {
-				segments.push(last);
-				format!("{base_url}/?search={}", segments.join("::"))
+		// link to unknown items of a known type on the page of the type
+		if link_type.is_none() && !self.no_method_anchors {
+			if let (Some(parent), Some(parent_link_type)) =
+				(segments.last(), parent_link_type)
+			{
+				let page = item_page(parent_link_type, parent);
+				let fragment = item_fragment(parent_link_type, &last);
+				if let (Some(page), Some(fragment)) = (page, fragment) {
+					let parent_uri = segments[.. segments.len() - 1]
+						.iter()
+						.map(|segment| format!("{segment}/"))
+						.collect::<String>();
+					return format!("{base_url}/{parent_uri}{page}#{fragment}");
+				}
 			}
 		}
+
+		segments.push(last);
+		format!("{base_url}/?search={}", segments.join("::"))
 	}
 }
 
 #[cfg(test)]
 mod tests {
+	fn test_input() -> crate::input::InputFile {
+		let mut input = crate::input::InputFile {
+			crate_name: "my-crate".into(),
+			lib_name: "my_crate".into(),
+			crate_version: semver::Version::new(0, 0, 0),
+			target_type: crate::input::TargetType::Lib,
+			repository: None,
+			license: None,
+			authors: Vec::new(),
+			rust_version: None,
+			keywords: Vec::new(),
+			categories: Vec::new(),
+			metadata: Default::default(),
+			rustdoc: String::new(),
+			rustdoc_spans: Default::default(),
+			dependencies: Default::default(),
+			scope: crate::input::Scope::prelude(cargo_metadata::Edition::E2021)
+		};
+		input.dependencies.insert(
+			"my_crate".into(),
+			crate::input::Dependency::new(
+				"my-crate".into(),
+				[semver::Comparator {
+					op: semver::Op::Exact,
+					major: 1,
+					minor: Some(2),
+					patch: Some(3),
+					pre: semver::Prerelease::EMPTY
+				}]
+				.into_iter()
+				.collect(),
+				"1.2.3".parse().unwrap()
+			)
+			.with_workspace_member(true)
+		);
+		input.dependencies.insert(
+			"other_crate".into(),
+			crate::input::Dependency::new(
+				"other-crate".into(),
+				"1".parse().unwrap(),
+				"1.0.0".parse().unwrap()
+			)
+			.with_workspace_member(true)
+		);
+		input.dependencies.insert(
+			"serde".into(),
+			crate::input::Dependency::new(
+				"serde".into(),
+				"1".parse().unwrap(),
+				"1.0.0".parse().unwrap()
+			)
+		);
+		input
+	}
+
+	fn diagnostic() -> crate::diagnostic::Diagnostic {
+		crate::diagnostic::Diagnostic::new(String::new(), String::new())
+	}
+
+	fn local_link(path: &str, link_type: crate::input::LinkType) -> String {
+		let mut links = super::Links::new("", "");
+		links.local_docs_base = Some("../".into());
+		links.build_link(
+			&syn::parse_str::<syn::Path>(path).unwrap(),
+			Some(link_type),
+			None,
+			&test_input(),
+			&mut diagnostic()
+		)
+	}
+
+	#[test]
+	fn test_crates_io_base_url() {
+		let mut links = super::Links::new("", "");
+		links.crates_io_base_url = Some("https://registry.example.org/".into());
+		assert_eq!(
+			links.build_link(
+				&syn::parse_str::<syn::Path>("serde").unwrap(),
+				Some(crate::input::LinkType::ExternCrate),
+				None,
+				&test_input(),
+				&mut diagnostic()
+			),
+			"https://registry.example.org/crates/serde/1.0.0"
+		);
+	}
+
+	#[test]
+	fn test_docs_base_urls() {
+		let mut links = super::Links::new("", "");
+		links.docs_rs_base_url = Some("https://docs.example.org/".into());
+		links.std_docs_base_url = Some("file:///usr/share/doc/rust/html".into());
+		let mut link = |path: &str, link_type| {
+			links.build_link(
+				&syn::parse_str::<syn::Path>(path).unwrap(),
+				Some(link_type),
+				None,
+				&test_input(),
+				&mut diagnostic()
+			)
+		};
+		assert_eq!(
+			link("serde::Serialize", crate::input::LinkType::Trait),
+			"https://docs.example.org/serde/1.0.0/serde/trait.Serialize.html"
+		);
+		assert_eq!(
+			link("std::string::String", crate::input::LinkType::Struct),
+			"file:///usr/share/doc/rust/html/std/string/struct.String.html"
+		);
+	}
+
+	#[test]
+	fn test_std_docs_rust_version() {
+		let mut input = test_input();
+		input.rust_version = Some(semver::Version::new(1, 74, 0));
+		let link = |std_docs_stable| {
+			let mut links = super::Links::new("", "");
+			links.std_docs_stable = std_docs_stable;
+			links.build_link(
+				&syn::parse_str::<syn::Path>("std::string::String").unwrap(),
+				Some(crate::input::LinkType::Struct),
+				None,
+				&input,
+				&mut diagnostic()
+			)
+		};
+		assert_eq!(
+			link(false),
+			"https://doc.rust-lang.org/1.74.0/std/string/struct.String.html"
+		);
+		assert_eq!(
+			link(true),
+			"https://doc.rust-lang.org/stable/std/string/struct.String.html"
+		);
+	}
+
+	#[test]
+	fn test_no_method_anchors() {
+		let mut links = super::Links::new("", "");
+		links.no_method_anchors = true;
+		assert_eq!(
+			links.build_link(
+				&syn::parse_str::<syn::Path>("::std::string::String::new").unwrap(),
+				None,
+				Some(crate::input::LinkType::Struct),
+				&test_input(),
+				&mut diagnostic()
+			),
+			"https://doc.rust-lang.org/stable/std/?search=string::String::new"
+		);
+	}
+
+	#[test]
+	fn test_local_docs_self() {
+		assert_eq!(
+			local_link("crate::Foo", crate::input::LinkType::Struct),
+			"../my_crate/struct.Foo.html"
+		);
+	}
+
+	#[test]
+	fn test_local_docs_sibling() {
+		assert_eq!(
+			local_link("other_crate::Bar", crate::input::LinkType::Enum),
+			"../other_crate/enum.Bar.html"
+		);
+		assert_eq!(
+			local_link("other_crate", crate::input::LinkType::ExternCrate),
+			"../other_crate/index.html"
+		);
+	}
+
+	#[test]
+	fn test_local_docs_non_member() {
+		assert_eq!(
+			local_link("serde::Serialize", crate::input::LinkType::Trait),
+			"https://docs.rs/serde/1.0.0/serde/trait.Serialize.html"
+		);
+	}
+
 	macro_rules! tests {
 		($($test:ident($input:literal, $($link_type:ident ,)? $expected:literal);)*) => {
 			$(
 				#[test]
 				fn $test() {
 					let mut links = super::Links::new("", "");
-					let mut input = crate::input::InputFile {
-						crate_name: "my-crate".into(),
-						crate_version: semver::Version::new(0, 0, 0),
-						target_type: crate::input::TargetType::Lib,
-						repository: None,
-						license: None,
-						rust_version: None,
-						rustdoc: String::new(),
-						dependencies: Default::default(),
-						scope: crate::input::Scope::prelude(cargo_metadata::Edition::E2021)
-					};
-					input.dependencies.insert(
-						"my_crate".into(),
-						crate::input::Dependency::new(
-							"my-crate".into(),
-							[semver::Comparator {
-								op: semver::Op::Exact,
-								major: 1,
-								minor: Some(2),
-								patch: Some(3),
-								pre: semver::Prerelease::EMPTY
-							}].into_iter().collect(),
-							"1.2.3".parse().unwrap()
-						)
-					);
+					let input = test_input();
 
 					#[allow(path_statements)]
 					let input_link_type = {
@@ -177,7 +431,9 @@ mod tests {
 						links.build_link(
 							&syn::parse_str::<syn::Path>(&path).unwrap(),
 							link_type,
-							&input
+							href.parent_link_type,
+							&input,
+							&mut diagnostic()
 						),
 						$expected
 					);
@@ -227,6 +483,16 @@ mod tests {
 			"https://docs.rs/my-crate/1.2.3/my_crate/static.MY_STATIC.html"
 		);
 
+		test_hyphenated_crate(
+			"my-crate::Foo", Struct,
+			"https://docs.rs/my-crate/1.2.3/my_crate/struct.Foo.html"
+		);
+
+		test_hyphenated_crate_root(
+			"my-crate",
+			"https://crates.io/crates/my-crate/1.2.3"
+		);
+
 		test_primitive(
 			"u8",
 			"https://doc.rust-lang.org/stable/std/primitive.u8.html"
@@ -237,6 +503,11 @@ mod tests {
 			"https://doc.rust-lang.org/stable/std/string/struct.String.html"
 		);
 
+		test_struct_full_path(
+			"std::string::String",
+			"https://doc.rust-lang.org/stable/std/string/struct.String.html"
+		);
+
 		test_trait(
 			"Clone",
 			"https://doc.rust-lang.org/stable/std/clone/trait.Clone.html"
@@ -244,7 +515,37 @@ mod tests {
 
 		test_trait_fn(
 			"Clone::clone",
-			"https://doc.rust-lang.org/stable/std/?search=clone::Clone::clone"
+			"https://doc.rust-lang.org/stable/std/clone/trait.Clone.html#method.clone"
+		);
+
+		test_struct_fn(
+			"String::new",
+			"https://doc.rust-lang.org/stable/std/string/struct.String.html#method.new"
+		);
+
+		test_enum_variant(
+			"Option::Some",
+			"https://doc.rust-lang.org/stable/std/option/enum.Option.html#variant.Some"
+		);
+
+		test_primitive_fn(
+			"u8::checked_add",
+			"https://doc.rust-lang.org/stable/std/primitive.u8.html#method.checked_add"
+		);
+
+		test_trait_assoc_type(
+			"Iterator::Item",
+			"https://doc.rust-lang.org/stable/std/iter/trait.Iterator.html#associatedtype.Item"
+		);
+
+		test_trait_assoc_type_fn(
+			"IntoIterator::into_iter",
+			"https://doc.rust-lang.org/stable/std/iter/trait.IntoIterator.html#method.into_iter"
+		);
+
+		test_struct_unknown_item(
+			"String::MAX",
+			"https://doc.rust-lang.org/stable/std/?search=string::String::MAX"
 		);
 
 		test_type(
@@ -50,6 +50,13 @@
 //! cargo doc2readme was created. If you need a newer/nightly Rust compiler, use the
 //! `ghcr.io/msrd0/cargo-doc2readme:nightly` docker image instead.
 //!
+//! If you install cargo doc2readme with the `network` feature, you can pass a url to
+//! `--template` to share one template between many repositories. The template is
+//! downloaded every time the readme is generated or checked, and a copy is kept in the
+//! temporary directory in case the download fails. Only use templates from sources you
+//! trust and always use https: whoever controls the url controls part of your readme,
+//! and changing the template makes `--check` fail in all repositories using it.
+//!
 //! # Features
 //!
 //!  - parse markdown from your rustdoc comments and embed it into your readme
@@ -80,22 +87,50 @@
 //! command-line arguments or to these stability guarantees. All other changes, including
 //! any changes to the Rust code, or bumping the MSRV, are not considered breaking changes.
 //!
+//! Changes to the markdown output increase the markdown version, which is recorded in
+//! the generated readme. To make sure that everyone working on a project generates the
+//! same output, you can pass `--min-markdown-version` to fail if the installed version
+//! of cargo doc2readme produces an older markdown version.
+//!
 //!  [crates.io]: https://crates.io
 //!  [GitHub]: https://github.com
 //!  [cargo-readme]: https://github.com/livioribeiro/cargo-readme
 //!  [docs.rs]: https://docs.rs
 
-use cargo_doc2readme::{diagnostic::Diagnostic, output, read_input, verify};
+use cargo_doc2readme::{
+	depinfo::DependencyInfo,
+	diagnostic::{self, ColorChoice, Diagnostic},
+	output::{
+		self, CodeblockFlags, EmitOptions, EmphasisChar, LinkStyle, ListMarker,
+		OutputFormat, SortLinks, ThematicBreak, TitleFormat
+	},
+	read_input, read_template,
+	verify::{self, CheckDepMode, CheckOptions, MessageFormat},
+	InputOptions
+};
+use cargo_metadata::{Edition, MetadataCommand};
 use clap::Parser;
+use is_terminal_polyfill::IsTerminal as _;
 use log::{error, info, warn, Level};
-use std::{env, fs::File, io, path::PathBuf, process::ExitCode};
+use notify::{RecursiveMode, Watcher as _};
+use simple_logger::SimpleLogger;
+use std::{
+	env,
+	ffi::OsStr,
+	fs::{self, File},
+	io,
+	path::{Path, PathBuf},
+	process::ExitCode,
+	sync::mpsc,
+	time::Duration
+};
 
 #[derive(Parser)]
 enum Subcommand {
 	Doc2readme(Args)
 }
 
-#[derive(Parser)]
+#[derive(Clone, Parser)]
 #[command(about, version)]
 struct Args {
 	/// Path to Cargo.toml.
@@ -106,12 +141,29 @@ struct Args {
 	#[arg(short, long)]
 	package: Option<String>,
 
-	/// Output File.
-	#[arg(short, long, default_value = "README.md")]
+	/// Generate the readme of every member of the workspace. This requires `--out-dir`,
+	/// as the readmes cannot all be written to the same file, and cargo to find the
+	/// members of the workspace.
+	#[arg(
+		long,
+		conflicts_with_all = ["package", "watch", "no_run_cargo"],
+		requires = "out_dir"
+	)]
+	workspace: bool,
+
+	/// Output File. If this is a directory, the readme is written to `README.md` inside
+	/// of it.
+	#[arg(short, long, default_value = DEFAULT_OUT)]
 	out: PathBuf,
 
+	/// Output directory. The readme is written to `<crate>.md` inside of it, and the
+	/// directory is created if it does not exist.
+	#[arg(long, value_name = "DIR", conflicts_with = "out")]
+	out_dir: Option<PathBuf>,
+
 	/// Template File. This is processed by minijinja. Look at the source code for
-	/// cargo-doc2readme for an example.
+	/// cargo-doc2readme for an example. This can also be a http(s) url if
+	/// cargo-doc2readme was built with the `network` feature.
 	#[arg(short, long, default_value = "README.j2")]
 	template: PathBuf,
 
@@ -120,37 +172,313 @@ struct Args {
 	#[arg(long)]
 	expand_macros: bool,
 
-	/// Space or comma separated list of features to activate. This will be ignored unless
-	/// `--expand-macros` is enabled, in which case it is being passed to cargo.
+	/// Space or comma separated list of features to activate. Can be given multiple
+	/// times, and is merged with the features from the package metadata. If
+	/// `--expand-macros` or `--verify-doctests` is enabled, this is being passed to
+	/// cargo. Otherwise, links to items that require features that are not active are not
+	/// resolved.
 	#[arg(short = 'F', long)]
-	features: Option<String>,
+	features: Vec<String>,
 
-	/// Activate all available features. This will be ignored unless `--expand-macros` is
-	/// enabled, in which case it is being passed to cargo.
+	/// Activate all available features. If `--expand-macros` or `--verify-doctests` is
+	/// enabled, this is being passed to cargo. Otherwise, links to items that require
+	/// features that are not active are not resolved.
 	#[arg(long)]
 	all_features: bool,
 
-	/// Do not activate the `default` feature. This will be ignored unless
-	/// `--expand-macros` is enabled, in which case it is being passed to cargo.
+	/// Do not activate the `default` feature. If `--expand-macros` or
+	/// `--verify-doctests` is enabled, this is being passed to cargo. Otherwise, links to
+	/// items that require features that are not active are not resolved.
 	#[arg(long)]
 	no_default_features: bool,
 
-	/// Prefer binary targets over library targets for rustdoc source.
-	#[arg(long, conflicts_with = "lib")]
-	bin: bool,
+	/// Directory for all generated artifacts. This will be ignored unless
+	/// `--expand-macros` or `--verify-doctests` is enabled, in which case it is being
+	/// passed to cargo.
+	#[arg(long)]
+	target_dir: Option<PathBuf>,
+
+	/// Abort if cargo does not finish expanding the macros within this many seconds, e.g.
+	/// because it is waiting for a file lock. This will be ignored unless
+	/// `--expand-macros` is enabled.
+	#[arg(long, value_name = "SECS")]
+	expand_timeout: Option<u64>,
+
+	/// The cargo profile to expand the macros with, e.g. `release` if the documentation
+	/// depends on `cfg(debug_assertions)`. This will be ignored unless `--expand-macros`
+	/// is enabled.
+	#[arg(long, value_name = "NAME")]
+	profile: Option<String>,
+
+	/// Read the `Cargo.toml` directly instead of running `cargo metadata`. This is faster,
+	/// but the versions of dependencies are unknown, so links to dependencies will point
+	/// to their latest version. Binaries in `src/bin/` and examples are only found if
+	/// they are listed in the `Cargo.toml`.
+	#[arg(long, conflicts_with_all = ["expand_macros", "verify_doctests"])]
+	no_run_cargo: bool,
+
+	/// Run the doc tests of the library using `cargo test --doc` before generating the
+	/// readme, and fail if any of them fails. This ensures that the examples in the
+	/// readme are valid, but can take a while as the crate has to be compiled.
+	#[arg(long)]
+	verify_doctests: bool,
+
+	/// Append the doc comments of `pub use` statements at the crate root to the crate
+	/// documentation, in the order they appear in the source code.
+	#[arg(long)]
+	include_reexport_docs: bool,
+
+	/// If the crate has no documentation, use the documentation of the first public item
+	/// that has some instead. This is useful for crates that only export a single type.
+	#[arg(long)]
+	fallback_to_item: bool,
+
+	/// Use the documentation of this module (e.g. `foo::bar`) instead of the crate
+	/// documentation. Only the doc comments of the module itself are used, and links are
+	/// still resolved relative to the crate root.
+	#[arg(long, value_name = "MODULE", conflicts_with_all = ["include_reexport_docs", "fallback_to_item"])]
+	doc_from: Option<String>,
+
+	/// Resolve links to private items like links to public items, similar to
+	/// `rustdoc --document-private-items`. This is intended for internal readmes, as the
+	/// documentation of private items is not available on docs.rs.
+	#[arg(long)]
+	include_private: bool,
+
+	/// Do not warn about glob uses. Items imported through a glob use are still not
+	/// used for link generation. This can also be set per crate with `allow-glob-use`
+	/// in `[package.metadata.doc2readme]`.
+	#[arg(long)]
+	allow_glob_use: bool,
+
+	/// Resolve links using the prelude of this edition (e.g. `2024`) instead of the
+	/// edition of the package.
+	#[arg(long, value_parser = parse_edition)]
+	edition: Option<Edition>,
+
+	/// Read the rustdoc from the library, binary or example target with this name.
+	#[arg(long, value_name = "NAME", conflicts_with_all = ["bin", "lib"])]
+	target_name: Option<String>,
+
+	/// Read the rustdoc from the target with this crate type, e.g. `cdylib` or `bin`.
+	#[arg(long, value_name = "TYPE", conflicts_with_all = ["bin", "lib", "target_name"])]
+	crate_type: Option<String>,
+
+	/// Prefer binary targets over library targets for rustdoc source. If a name is
+	/// given, the binary target with this name is used.
+	#[arg(long, value_name = "NAME", num_args = 0 ..= 1, conflicts_with = "lib")]
+	bin: Option<Option<String>>,
 
 	/// Prefer library targets over binary targets for rustdoc source. This is the default.
 	#[arg(long, conflicts_with = "bin")]
 	lib: bool,
 
+	/// Sort the link definitions at the end of the readme by their reference name or
+	/// by their destination url. By default, the order is determined by the reference
+	/// names, sorted as strings.
+	#[arg(long, value_enum)]
+	sort_links: Option<SortLinks>,
+
+	/// The style of the reference names of the links at the end of the readme.
+	/// `reference` uses names like `__link0`, `footnote` numbers the links in the order
+	/// of their first appearance, like `[1]`.
+	#[arg(long, value_enum, default_value = "reference")]
+	link_style: LinkStyle,
+
+	/// The marker of the items of unordered lists in the readme.
+	#[arg(long, value_enum, default_value = "asterisk")]
+	list_marker: ListMarker,
+
+	/// The character used for emphasis in the readme. Strong emphasis uses the same
+	/// character twice.
+	#[arg(long, value_enum, default_value = "asterisk")]
+	emphasis_char: EmphasisChar,
+
+	/// The style of thematic breaks in the readme.
+	#[arg(long, value_enum, default_value = "dash")]
+	thematic_break: ThematicBreak,
+
+	/// What to do with the flags of code blocks that are meant for rustdoc, like
+	/// `should_panic` or `no_run`. By default, they are removed.
+	#[arg(long, value_enum, default_value = "strip")]
+	codeblock_flags: CodeblockFlags,
+
+	/// Wrap the readme in a fenced code block with this language, e.g. `markdown`, to
+	/// show the raw markdown when it is embedded in another document.
+	#[arg(long, value_name = "LANG")]
+	wrap_in_fence: Option<String>,
+
+	/// The format of the readme. Links are inlined for formats other than markdown, and
+	/// the dependency info can only be written with `--dep-info-sidecar`.
+	#[arg(long, value_enum, default_value = "markdown")]
+	output_format: OutputFormat,
+
+	/// Keep the level of the first heading in the rustdoc, so that e.g. a leading
+	/// `# Crate Name` becomes the title of the readme. All other headings are still
+	/// increased by one level. This is best combined with a template that does not
+	/// emit its own `# {{ title }}` heading.
+	#[arg(long)]
+	keep_first_heading: bool,
+
+	/// Remove a level 1 heading at the start of the crate documentation if its text is
+	/// the crate name or the configured title, as the default template already starts
+	/// with the title.
+	#[arg(long)]
+	dedup_title: bool,
+
+	/// Link to the documentation of this crate and the other members of its workspace
+	/// relative to this path or url (e.g. `../other_crate/index.html` for `..`) instead
+	/// of docs.rs. This is useful if the documentation of all crates is hosted together.
+	#[arg(long, value_name = "PATH")]
+	local_docs_base: Option<String>,
+
+	/// The base url for links to crates, e.g. the front-end of a private registry. The
+	/// default is `https://crates.io`.
+	#[arg(long, value_name = "URL")]
+	crates_io_base_url: Option<String>,
+
+	/// The base url for links to the documentation of crates, e.g. a mirror of docs.rs
+	/// for a private registry. The default is `https://docs.rs`.
+	#[arg(long, value_name = "URL")]
+	docs_rs_base_url: Option<String>,
+
+	/// The base url for links to the standard library, e.g. the documentation installed
+	/// by rustup. The default is `https://doc.rust-lang.org/stable`.
+	#[arg(long, value_name = "URL")]
+	std_docs_base_url: Option<String>,
+
+	/// Link to the latest stable standard library. By default, links to the standard
+	/// library point to the documentation of the `rust-version` of the crate, if any.
+	/// The version is always written in full, e.g. `1.74.0` for `rust-version = "1.74"`,
+	/// because the documentation of old releases is only published under full versions.
+	#[arg(long, conflicts_with = "std_docs_base_url")]
+	std_docs_stable: bool,
+
+	/// Do not guess that links like `Type::name` point to a method or enum variant of a
+	/// known type, and link to the search of the documentation instead.
+	#[arg(long)]
+	no_method_anchors: bool,
+
+	/// Write only the readme generated from the rustdoc and its links, without a title,
+	/// badges or anything else from a template.
+	#[arg(long, conflicts_with = "template")]
+	bare: bool,
+
+	/// Write only the definitions of the links found in the rustdoc, including the
+	/// dependency info, so that they can be added to a readme written by other tools.
+	#[arg(long, conflicts_with_all = ["template", "bare", "output_format", "wrap_in_fence"])]
+	links_only: bool,
+
+	/// Keep the lines of code blocks that are hidden in the documentation, e.g. to show
+	/// the full example. Only their leading `# ` is removed, so that the code still
+	/// compiles.
+	#[arg(long)]
+	show_hidden_lines: bool,
+
+	/// Append a `<!-- doc2readme-hash: HASH -->` comment with the blake3 hash of
+	/// everything before it, so that other tools can detect changes to the readme
+	/// without reading the dependency info.
+	#[arg(long, conflicts_with = "output_format")]
+	emit_content_hash: bool,
+
+	/// Pass a title in this format to the template instead of the crate name or the title
+	/// from the package metadata. The placeholders `{crate}`, `{version}` and `{title}`
+	/// are replaced by the crate name, the crate version and the title from the package
+	/// metadata, e.g. `{crate} v{version}`.
+	#[arg(long, value_name = "FORMAT")]
+	title_format: Option<TitleFormat>,
+
+	/// Store the dependency info in a sidecar file next to the output file (e.g.
+	/// `README.md.doc2readme`) instead of the output file itself. This keeps the readme
+	/// free of the dependency info line, but the sidecar file has to be committed
+	/// alongside the readme for `--check` to be able to use it. Without dependency info,
+	/// `--check` has to fall back to a full comparison of the readme.
+	#[arg(long)]
+	dep_info_sidecar: bool,
+
+	/// How `--check` compares the dependency versions that the readme links to with the
+	/// current ones. `compatible` accepts any version that satisfies the version
+	/// requirement, `exact` requires the version that the dependency resolves to.
+	#[arg(long, value_enum, default_value = "compatible")]
+	check_dep_mode: CheckDepMode,
+
+	/// Make `--check` compare the content of the readme with the output that would be
+	/// generated, ignoring the dependency info. This avoids failures when the readme was
+	/// generated by a different version of this tool.
+	#[arg(long, requires = "check")]
+	ignore_dep_info: bool,
+
+	/// How `--check` reports its result. `json` prints an object like
+	/// `{"status":"output_changed"}` to stdout.
+	#[arg(long, value_enum, default_value = "human", requires = "check")]
+	message_format: MessageFormat,
+
 	/// Verify that the output file is (reasonably) up to date, and fail
 	/// if it needs updating. The output file will not be changed.
 	#[arg(long)]
 	check: bool,
 
+	/// Write the crate name, version, target type, dependencies and resolved links as
+	/// JSON to stdout instead of generating the readme. This is intended for tooling,
+	/// the format of the JSON output is not stable.
+	#[arg(long, conflicts_with = "check")]
+	dump_input: bool,
+
+	/// Write a JSON object that maps the text of every heading in the readme to the
+	/// anchor that GitHub creates for it to this file, e.g. to link to sections of the
+	/// readme from other files. Headings from the template are not included.
+	#[arg(long, value_name = "FILE", conflicts_with_all = ["check", "dump_input"])]
+	emit_anchors: Option<PathBuf>,
+
+	/// Only replace the region between the `<!-- cargo-doc2readme start -->` and
+	/// `<!-- cargo-doc2readme end -->` markers in the output file, keeping everything
+	/// else. With `--check`, only that region is compared.
+	#[arg(long)]
+	markers: bool,
+
+	/// Keep running and re-generate the readme whenever the template or the source code
+	/// changes. This is useful while working on the template.
+	#[arg(long, conflicts_with_all = ["check", "dump_input"])]
+	watch: bool,
+
+	/// Fail if this version of cargo-doc2readme produces a markdown version older than
+	/// this. This is useful to ensure that everyone generates the same output.
+	#[arg(long, value_name = "VERSION")]
+	min_markdown_version: Option<u8>,
+
+	/// Only render the template with placeholder values instead of the data of a crate,
+	/// and report any errors in the template. This does not read any cargo project.
+	#[arg(long, conflicts_with_all = ["check", "dump_input", "watch"])]
+	validate_template: bool,
+
+	/// Fail if any warnings are reported. Warnings about the generated readme are
+	/// reported after it was written.
+	#[arg(long)]
+	deny_warnings: bool,
+
+	/// Fail if any link in the rustdoc cannot be resolved to an item in scope or a
+	/// dependency. Unlike --deny-warnings, this ignores all other warnings.
+	#[arg(long)]
+	deny_broken_links: bool,
+
 	/// Enable verbose output.
 	#[arg(short, long)]
-	verbose: bool
+	verbose: bool,
+
+	/// Only print warnings and errors. Together with --check, nothing is printed if
+	/// the readme is up to date.
+	#[arg(short, long, conflicts_with = "verbose")]
+	quiet: bool,
+
+	/// When to use colors for warnings and errors. By default, the `NO_COLOR`,
+	/// `CLICOLOR_FORCE` and `CLICOLOR` environment variables are respected, and colors
+	/// are only used if stderr is a terminal.
+	#[arg(long, value_name = "WHEN", default_value = "auto")]
+	color: ColorChoice,
+
+	/// Never use colors, the same as --color=never.
+	#[arg(long, conflicts_with = "color")]
+	no_color: bool
 }
 
 #[derive(Parser)]
@@ -167,6 +495,24 @@ macro_rules! exit_on_err {
 	};
 }
 
+macro_rules! exit_on_warn {
+	($args:ident, $diagnostics:ident) => {
+		if $args.deny_warnings && $diagnostics.has_warnings() {
+			error!("Aborting due to warnings, as --deny-warnings was passed");
+			return ExitCode::FAILURE;
+		}
+	};
+}
+
+macro_rules! emit_on_err {
+	($result:ident) => {
+		if let Err(e) = $result {
+			error!("Unable to generate the readme: {e:#}");
+			return ExitCode::FAILURE;
+		}
+	};
+}
+
 fn main() -> ExitCode {
 	let args = match env::args().nth(1) {
 		Some(subcmd) if subcmd == "doc2readme" => match CmdLine::parse().cmd {
@@ -175,57 +521,248 @@ fn main() -> ExitCode {
 		_ => Args::parse()
 	};
 
+	let color = match args.no_color {
+		true => ColorChoice::Never,
+		false => args.color
+	};
+	let color = color.use_color(|name| env::var_os(name), io::stderr().is_terminal());
+	diagnostic::set_color(color);
+
 	// check input
-	if !args.expand_macros {
+	if !args.expand_macros && !args.verify_doctests {
 		let mut diag = Diagnostic::new(String::new(), String::new());
-		if args.features.is_some() {
-			diag.warn("--features option has no effect without the --expand-macros flag")
-		}
-		if args.no_default_features {
-			diag.warn("--no-default-features flag has no effect without the --expand-macros flag")
+		if args.target_dir.is_some() {
+			diag.warn("--target-dir option has no effect without the --expand-macros or --verify-doctests flag")
 		}
-		if args.all_features {
+		diag.print().unwrap();
+	}
+	if !args.expand_macros {
+		let mut diag = Diagnostic::new(String::new(), String::new());
+		if args.expand_timeout.is_some() {
 			diag.warn(
-				"--all-features flag has no effect without the --expand-macros flag"
+				"--expand-timeout option has no effect without the --expand-macros flag"
 			)
 		}
+		if args.profile.is_some() {
+			diag.warn("--profile option has no effect without the --expand-macros flag")
+		}
+		diag.print().unwrap();
+	}
+	if args.dep_info_sidecar && args.out.to_str() == Some("-") {
+		let mut diag = Diagnostic::new(String::new(), String::new());
+		diag.warn("--dep-info-sidecar flag has no effect when writing to stdout");
 		diag.print().unwrap();
 	}
+	if args.markers && args.out.to_str() == Some("-") && !args.check {
+		let mut diag = Diagnostic::new(String::new(), String::new());
+		diag.warn("--markers flag has no effect when writing to stdout");
+		diag.print().unwrap();
+	}
+
+	SimpleLogger::new()
+		.with_level(
+			if args.verbose {
+				Level::Debug
+			} else if args.quiet {
+				Level::Warn
+			} else {
+				Level::Info
+			}
+			.to_level_filter()
+		)
+		.with_colors(color)
+		.init()
+		.expect("Failed to initialize logger");
+
+	if let Some(min_markdown_version) = args.min_markdown_version {
+		let markdown_version = DependencyInfo::markdown_version();
+		if markdown_version < min_markdown_version {
+			error!(
+				"This version of cargo-doc2readme produces markdown version {markdown_version}, \
+				 but at least version {min_markdown_version} is required. Please upgrade \
+				 cargo-doc2readme."
+			);
+			return ExitCode::FAILURE;
+		}
+	}
 
-	simple_logger::init_with_level(
-		args.verbose.then(|| Level::Debug).unwrap_or(Level::Info)
-	)
-	.expect("Failed to initialize logger");
-
-	let (input_file, template, diagnostics) = read_input(
-		args.manifest_path,
-		args.package,
-		args.bin,
-		args.expand_macros,
-		args.template,
-		args.features,
-		args.no_default_features,
-		args.all_features
-	);
+	if args.validate_template {
+		return validate_template(&args.template);
+	}
+
+	if args.watch {
+		watch(&args)
+	} else if args.workspace {
+		run_workspace(&args)
+	} else {
+		run(&args)
+	}
+}
+
+/// Generate or check the readme of every member of the workspace.
+fn run_workspace(args: &Args) -> ExitCode {
+	let mut cmd = MetadataCommand::new();
+	cmd.no_deps();
+	if let Some(path) = &args.manifest_path {
+		cmd.manifest_path(path);
+	}
+	let metadata = match cmd.exec() {
+		Ok(metadata) => metadata,
+		Err(e) => {
+			error!("Failed to get cargo metadata: {e:#}");
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let mut exit_code = ExitCode::SUCCESS;
+	for pkg in metadata.workspace_packages() {
+		let args = Args {
+			package: Some(pkg.name.clone()),
+			workspace: false,
+			..args.clone()
+		};
+		if run(&args) != ExitCode::SUCCESS {
+			exit_code = ExitCode::FAILURE;
+		}
+	}
+	exit_code
+}
+
+/// Render the template without a crate and report any errors.
+fn validate_template(path: &Path) -> ExitCode {
+	let template = match read_template(path) {
+		Ok(template) => template,
+		Err(e) => {
+			error!("Failed to read template: {e}");
+			return ExitCode::FAILURE;
+		}
+	};
+	let mut diagnostics =
+		Diagnostic::new(path.display().to_string(), template.clone().into_owned());
+	output::validate_template(&template, &mut diagnostics);
 	diagnostics.print().unwrap();
 	exit_on_err!(diagnostics);
+	info!("Template is valid");
+	ExitCode::SUCCESS
+}
+
+/// Read the input and generate or check the readme once.
+fn run(args: &Args) -> ExitCode {
+	let (input_file, template, diagnostics) = read_input(InputOptions {
+		manifest_path: args.manifest_path.clone(),
+		package: args.package.clone(),
+		prefer_bin: args.bin.is_some(),
+		bin_name: args.bin.clone().flatten(),
+		expand_macros: args.expand_macros,
+		template: args.template.clone(),
+		features: (!args.features.is_empty()).then(|| args.features.join(",")),
+		no_default_features: args.no_default_features,
+		all_features: args.all_features,
+		target_dir: args.target_dir.clone(),
+		expand_timeout: args.expand_timeout.map(Duration::from_secs),
+		profile: args.profile.clone(),
+		no_run_cargo: args.no_run_cargo,
+		include_reexport_docs: args.include_reexport_docs,
+		fallback_to_item: args.fallback_to_item,
+		include_private: args.include_private,
+		allow_glob_use: args.allow_glob_use,
+		verify_doctests: args.verify_doctests,
+		edition: args.edition,
+		target_name: args.target_name.clone(),
+		crate_type: args.crate_type.clone(),
+		doc_from: args.doc_from.clone()
+	});
+	diagnostics.print().unwrap();
+	exit_on_err!(diagnostics);
+	exit_on_warn!(args, diagnostics);
+
+	let options = EmitOptions {
+		sort_links: args.sort_links,
+		keep_first_heading: args.keep_first_heading,
+		dedup_title: args.dedup_title,
+		local_docs_base: args.local_docs_base.clone(),
+		crates_io_base_url: args.crates_io_base_url.clone(),
+		docs_rs_base_url: args.docs_rs_base_url.clone(),
+		std_docs_base_url: args.std_docs_base_url.clone(),
+		std_docs_stable: args.std_docs_stable,
+		title_format: args.title_format.clone(),
+		no_method_anchors: args.no_method_anchors,
+		bare: args.bare,
+		links_only: args.links_only,
+		show_hidden_lines: args.show_hidden_lines,
+		emit_content_hash: args.emit_content_hash,
+		link_style: args.link_style,
+		list_marker: args.list_marker,
+		emphasis_char: args.emphasis_char,
+		thematic_break: args.thematic_break,
+		codeblock_flags: args.codeblock_flags,
+		wrap_in_fence: args.wrap_in_fence.clone(),
+		output_format: args.output_format
+	};
 
 	let out_is_stdout = args.out.to_str() == Some("-");
-	let out = if !out_is_stdout && args.out.is_relative() {
-		env::current_dir().unwrap().join(args.out)
-	} else {
-		args.out
+	let out = match &args.out_dir {
+		Some(out_dir) => {
+			let out_dir = env::current_dir().unwrap().join(out_dir);
+			if !args.check {
+				if let Err(e) = fs::create_dir_all(&out_dir) {
+					error!("Unable to create directory {}: {e}", out_dir.display());
+					return ExitCode::FAILURE;
+				}
+			}
+			out_dir.join(format!("{}.md", input_file.crate_name))
+		},
+		None if !out_is_stdout && args.out.is_relative() => {
+			out_file(env::current_dir().unwrap().join(&args.out))
+		},
+		None => out_file(args.out.clone())
 	};
 
-	if args.check {
+	let dep_info_path = DependencyInfo::sidecar_path(&out);
+	if args.dump_input {
+		let mut diagnostics = diagnostics.empty_clone();
+		output::dump_input(
+			&input_file,
+			&template,
+			&options,
+			&mut io::stdout(),
+			&mut diagnostics
+		)
+		.expect("Unable to write to stdout!");
+		diagnostics.print().unwrap();
+		ExitCode::SUCCESS
+	} else if args.check {
 		info!("Reading {}", out.display());
 		match File::open(&out) {
 			Ok(mut file) => {
-				let check = verify::check_up2date(input_file, &template, &mut file)
-					.expect("Failed to check readme");
-				check
-					.print(out.display().to_string())
-					.expect("Unable to write to stderr");
+				let mut dep_info_file = match File::open(&dep_info_path) {
+					Ok(file) => Some(file),
+					Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+					Err(e) => {
+						error!("Unable to open file {}: {e}", dep_info_path.display());
+						return ExitCode::FAILURE;
+					}
+				};
+				let check = verify::check_up2date(
+					input_file,
+					&template,
+					&options,
+					&CheckOptions {
+						dep_mode: args.check_dep_mode,
+						markers: args.markers,
+						ignore_dep_info: args.ignore_dep_info
+					},
+					&mut file,
+					dep_info_file.as_mut().map(|file| file as _)
+				)
+				.expect("Failed to check readme");
+				let filename = out.display().to_string();
+				match (args.message_format, args.quiet) {
+					(MessageFormat::Json, _) => check.print_json(),
+					(MessageFormat::Human, true) => check.print_quiet(filename),
+					(MessageFormat::Human, false) => check.print(filename)
+				}
+				.expect("Unable to write the check result");
 				check.into()
 			},
 			Err(e) if e.kind() == io::ErrorKind::NotFound => {
@@ -238,20 +775,159 @@ fn main() -> ExitCode {
 			}
 		}
 	} else {
+		if let Some(anchors_path) = &args.emit_anchors {
+			info!("Writing anchors to {}", anchors_path.display());
+			let mut file =
+				File::create(anchors_path).expect("Unable to create anchors file");
+			output::emit_anchors(&input_file, &template, &options, &mut file)
+				.expect("Unable to write anchors file");
+		}
+
+		let mut diagnostics = diagnostics.empty_clone();
 		if out_is_stdout {
 			info!("Writing README to stdout");
-			output::emit(input_file, &template, &mut io::stdout())
-				.expect("Unable to write to stdout!");
+			let result = output::emit(
+				input_file,
+				&template,
+				&options,
+				&mut io::stdout(),
+				None,
+				&mut diagnostics
+			);
+			emit_on_err!(result);
 		} else {
 			info!("Writing README to {}", out.display());
-			let mut file = File::create(&out).expect("Unable to create output file");
-			output::emit(input_file, &template, &mut file)
-				.expect("Unable to write output file");
+			let mut dep_info_file = args.dep_info_sidecar.then(|| {
+				info!("Writing dependency info to {}", dep_info_path.display());
+				File::create(&dep_info_path)
+					.expect("Unable to create dependency info file")
+			});
+			if args.markers {
+				let content = match fs::read_to_string(&out) {
+					Ok(content) => content,
+					Err(e) => {
+						error!("Unable to read file {}: {e}", out.display());
+						return ExitCode::FAILURE;
+					}
+				};
+				let mut readme = Vec::new();
+				let result = output::emit(
+					input_file,
+					&template,
+					&options,
+					&mut readme,
+					dep_info_file.as_mut().map(|file| file as _),
+					&mut diagnostics
+				);
+				emit_on_err!(result);
+				let readme =
+					String::from_utf8(readme).expect("Readme is not valid UTF-8");
+				match output::replace_marker_region(&content, &readme) {
+					Ok(content) => {
+						fs::write(&out, content).expect("Unable to write output file")
+					},
+					Err(e) => {
+						error!("{e} in {}", out.display());
+						return ExitCode::FAILURE;
+					}
+				}
+			} else {
+				let mut file = File::create(&out).expect("Unable to create output file");
+				let result = output::emit(
+					input_file,
+					&template,
+					&options,
+					&mut file,
+					dep_info_file.as_mut().map(|file| file as _),
+					&mut diagnostics
+				);
+				emit_on_err!(result);
+			}
 		};
+		diagnostics.print().unwrap();
+		exit_on_warn!(args, diagnostics);
+		if args.deny_broken_links && diagnostics.has_broken_links() {
+			error!("Aborting due to broken links, as --deny-broken-links was passed");
+			return ExitCode::FAILURE;
+		}
 		ExitCode::SUCCESS
 	}
 }
 
+/// Generate the readme, and re-generate it whenever the template or the source code
+/// changes. This never returns unless the file system cannot be watched.
+fn watch(args: &Args) -> ExitCode {
+	let dir = match &args.manifest_path {
+		Some(path) => path.parent().map(Path::to_owned).unwrap_or_default(),
+		None => env::current_dir().unwrap()
+	};
+	let template_name = args.template.file_name().map(OsStr::to_owned);
+
+	let (tx, rx) = mpsc::channel();
+	let mut watcher = match notify::recommended_watcher(tx) {
+		Ok(watcher) => watcher,
+		Err(e) => {
+			error!("Unable to watch for changes: {e}");
+			return ExitCode::FAILURE;
+		}
+	};
+	if let Err(e) = watcher.watch(&dir, RecursiveMode::Recursive) {
+		error!("Unable to watch {}: {e}", dir.display());
+		return ExitCode::FAILURE;
+	}
+
+	// only the source code, manifests and the template are relevant. this also ensures
+	// that writing the readme does not trigger another run
+	let is_relevant = |path: &Path| {
+		!path.components().any(|c| c.as_os_str() == "target")
+			&& (matches!(
+				path.extension().and_then(OsStr::to_str),
+				Some("rs" | "toml")
+			) || path.file_name().map(OsStr::to_owned) == template_name)
+	};
+
+	loop {
+		run(args);
+		info!("Waiting for changes in {}", dir.display());
+		loop {
+			match rx.recv() {
+				Ok(Ok(event)) if event.paths.iter().any(|path| is_relevant(path)) => {
+					break
+				},
+				Ok(Ok(_)) => {},
+				Ok(Err(e)) => warn!("Error while watching for changes: {e}"),
+				Err(_) => return ExitCode::FAILURE
+			}
+		}
+		// editors tend to write files in several steps, so we wait for things to settle
+		while rx.recv_timeout(Duration::from_millis(100)).is_ok() {}
+	}
+}
+
+/// Parse an edition like cargo does in the `Cargo.toml`.
+fn parse_edition(edition: &str) -> Result<Edition, String> {
+	match edition {
+		"2015" => Ok(Edition::E2015),
+		"2018" => Ok(Edition::E2018),
+		"2021" => Ok(Edition::E2021),
+		"2024" => Ok(Edition::_E2024),
+		_ => Err("expected one of 2015, 2018, 2021 or 2024".into())
+	}
+}
+
+/// The default name of the output file.
+const DEFAULT_OUT: &str = "README.md";
+
+/// Return the path of the output file. If `out` is a directory, the readme is written
+/// to a file with the default name inside of it.
+fn out_file(out: PathBuf) -> PathBuf {
+	if out.is_dir() {
+		out.join(DEFAULT_OUT)
+	} else {
+		out
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -261,4 +937,57 @@ mod tests {
 		use clap::CommandFactory;
 		Args::command().debug_assert()
 	}
+
+	#[test]
+	fn out_file_in_directory() {
+		let dir = env::temp_dir();
+		assert_eq!(out_file(dir.clone()), dir.join("README.md"));
+	}
+
+	#[test]
+	fn out_file_not_a_directory() {
+		let file = env::temp_dir()
+			.join("cargo-doc2readme-no-such-dir")
+			.join("OUT.md");
+		assert_eq!(out_file(file.clone()), file);
+	}
+
+	#[test]
+	fn workspace_out_dir() {
+		let out_dir = env::temp_dir().join("cargo-doc2readme-workspace-out-dir");
+		let _ = fs::remove_dir_all(&out_dir);
+		let manifest_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+			.join("tests")
+			.join("workspace-out-dir")
+			.join("Cargo.toml");
+		let args = Args::parse_from([
+			"cargo-doc2readme".as_ref(),
+			"--workspace".as_ref(),
+			"--manifest-path".as_ref(),
+			manifest_path.as_os_str(),
+			"--out-dir".as_ref(),
+			out_dir.as_os_str()
+		]);
+		assert!(run_workspace(&args) == ExitCode::SUCCESS);
+
+		for krate in ["alpha", "beta"] {
+			let readme = fs::read_to_string(out_dir.join(format!("{krate}.md"))).unwrap();
+			assert!(readme.contains(&format!("This is the {krate} crate.")));
+		}
+	}
+
+	#[test]
+	fn workspace_conflicts_with_no_run_cargo() {
+		let result = Args::try_parse_from([
+			"cargo-doc2readme",
+			"--workspace",
+			"--out-dir",
+			"out",
+			"--no-run-cargo"
+		]);
+		assert!(matches!(
+			result,
+			Err(err) if err.kind() == clap::error::ErrorKind::ArgumentConflict
+		));
+	}
 }
@@ -0,0 +1,470 @@
+use anyhow::{bail, Context};
+use cargo_metadata::{Metadata, MetadataCommand};
+use monostate::MustBe;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+	collections::BTreeMap,
+	env, fs,
+	path::{Path, PathBuf}
+};
+
+/// The parts of a `Cargo.toml` that we need to emulate `cargo metadata`.
+#[derive(Deserialize)]
+struct Manifest {
+	package: ManifestPackage,
+	lib: Option<ManifestTarget>,
+	#[serde(default)]
+	bin: Vec<ManifestTarget>,
+	#[serde(default)]
+	example: Vec<ManifestTarget>,
+	#[serde(default)]
+	dependencies: BTreeMap<String, ManifestDependency>,
+	#[serde(default)]
+	features: BTreeMap<String, Vec<String>>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ManifestPackage {
+	name: String,
+	version: Option<MaybeInherited<String>>,
+	authors: Option<MaybeInherited<Vec<String>>>,
+	edition: Option<MaybeInherited<String>>,
+	license: Option<MaybeInherited<String>>,
+	repository: Option<MaybeInherited<String>>,
+	rust_version: Option<MaybeInherited<String>>,
+	keywords: Option<MaybeInherited<Vec<String>>>,
+	categories: Option<MaybeInherited<Vec<String>>>,
+	metadata: Option<serde_json::Value>,
+	workspace: Option<PathBuf>
+}
+
+/// A package field that can either be set directly or be inherited from the workspace
+/// using `field.workspace = true`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaybeInherited<T> {
+	Inherited {
+		#[allow(dead_code)]
+		workspace: MustBe!(true)
+	},
+	Value(T)
+}
+
+impl<T> MaybeInherited<T> {
+	fn is_inherited(&self) -> bool {
+		matches!(self, Self::Inherited { .. })
+	}
+}
+
+/// The parts of the workspace root's `Cargo.toml` that members can inherit from.
+#[derive(Deserialize)]
+struct WorkspaceManifest {
+	workspace: Option<Workspace>
+}
+
+#[derive(Default, Deserialize)]
+struct Workspace {
+	#[serde(default)]
+	package: WorkspacePackage,
+	#[serde(default)]
+	dependencies: BTreeMap<String, ManifestDependency>
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct WorkspacePackage {
+	version: Option<String>,
+	authors: Option<Vec<String>>,
+	edition: Option<String>,
+	license: Option<String>,
+	repository: Option<String>,
+	rust_version: Option<String>,
+	keywords: Option<Vec<String>>,
+	categories: Option<Vec<String>>
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ManifestTarget {
+	name: Option<String>,
+	path: Option<PathBuf>,
+	#[serde(default)]
+	proc_macro: bool,
+	crate_type: Option<Vec<String>>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ManifestDependency {
+	Version(String),
+	Detailed(ManifestDependencyDetail)
+}
+
+impl ManifestDependency {
+	fn into_detail(self) -> ManifestDependencyDetail {
+		match self {
+			Self::Version(version) => ManifestDependencyDetail {
+				version: Some(version),
+				package: None,
+				path: None,
+				optional: false,
+				default_features: None,
+				features: Vec::new(),
+				workspace: false
+			},
+			Self::Detailed(dep) => dep
+		}
+	}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ManifestDependencyDetail {
+	version: Option<String>,
+	package: Option<String>,
+	path: Option<PathBuf>,
+	#[serde(default)]
+	optional: bool,
+	default_features: Option<bool>,
+	#[serde(default)]
+	features: Vec<String>,
+	#[serde(default)]
+	workspace: bool
+}
+
+/// Find the `Cargo.toml` in the current directory or any of its parents, like cargo
+/// does when no manifest path is specified.
+fn find_manifest() -> anyhow::Result<PathBuf> {
+	let cwd = env::current_dir()?;
+	match cwd
+		.ancestors()
+		.map(|dir| dir.join("Cargo.toml"))
+		.find(|path| path.exists())
+	{
+		Some(path) => Ok(path),
+		None => bail!(
+			"Unable to find Cargo.toml in {} or any parent",
+			cwd.display()
+		)
+	}
+}
+
+/// Find and read the workspace root's `Cargo.toml`. Like cargo, this uses the
+/// `package.workspace` key if present, and otherwise searches the package directory and
+/// its parents for a `Cargo.toml` with a `[workspace]` section.
+fn read_workspace(
+	dir: &Path,
+	workspace: Option<&Path>
+) -> anyhow::Result<(PathBuf, Workspace)> {
+	let candidates = match workspace {
+		Some(workspace) => vec![dir.join(workspace)],
+		None => dir.ancestors().map(Path::to_path_buf).collect()
+	};
+	for ws_dir in candidates {
+		let ws_manifest_path = ws_dir.join("Cargo.toml");
+		if !ws_manifest_path.exists() {
+			continue;
+		}
+		let ws_manifest: WorkspaceManifest =
+			toml::from_str(&fs::read_to_string(&ws_manifest_path).with_context(
+				|| format!("Failed to read {}", ws_manifest_path.display())
+			)?)
+			.with_context(|| format!("Failed to parse {}", ws_manifest_path.display()))?;
+		if let Some(workspace) = ws_manifest.workspace {
+			return Ok((ws_dir, workspace));
+		}
+	}
+	bail!(
+		"Unable to find the workspace root of {} to inherit values from",
+		dir.display()
+	)
+}
+
+/// Resolve a package field that might be inherited from the workspace.
+fn inherit<T>(
+	value: Option<MaybeInherited<T>>,
+	key: &str,
+	workspace: Option<&mut WorkspacePackage>,
+	get: impl FnOnce(&mut WorkspacePackage) -> Option<T>
+) -> anyhow::Result<Option<T>> {
+	Ok(match value {
+		Some(MaybeInherited::Value(value)) => Some(value),
+		Some(MaybeInherited::Inherited { .. }) => {
+			Some(workspace.and_then(get).with_context(|| {
+				format!("`{key}` was not defined in `workspace.package`")
+			})?)
+		},
+		None => None
+	})
+}
+
+fn target_json<P: AsRef<Path>>(
+	name: String,
+	kind: Vec<String>,
+	crate_types: Vec<String>,
+	src_path: P,
+	edition: &str
+) -> serde_json::Value {
+	json!({
+		"name": name,
+		"kind": kind,
+		"crate_types": crate_types,
+		"src_path": src_path.as_ref(),
+		"edition": edition
+	})
+}
+
+/// Read the package metadata directly from the `Cargo.toml` without invoking cargo. The
+/// returned metadata contains only the package itself, therefore no versions of its
+/// dependencies are known. Values inherited using `workspace = true` are read from the
+/// workspace root's `Cargo.toml`.
+pub fn read_metadata(manifest_path: Option<&Path>) -> anyhow::Result<Metadata> {
+	let manifest_path = match manifest_path {
+		Some(path) => path.to_owned(),
+		None => find_manifest()?
+	};
+	let dir = manifest_path
+		.parent()
+		.context("Manifest path has no parent directory")?;
+	let manifest: Manifest = toml::from_str(
+		&fs::read_to_string(&manifest_path)
+			.with_context(|| format!("Failed to read {}", manifest_path.display()))?
+	)
+	.with_context(|| format!("Failed to parse {}", manifest_path.display()))?;
+	let pkg = manifest.package;
+
+	let inherits = [
+		pkg.version.as_ref().map(MaybeInherited::is_inherited),
+		pkg.authors.as_ref().map(MaybeInherited::is_inherited),
+		pkg.edition.as_ref().map(MaybeInherited::is_inherited),
+		pkg.license.as_ref().map(MaybeInherited::is_inherited),
+		pkg.repository.as_ref().map(MaybeInherited::is_inherited),
+		pkg.rust_version.as_ref().map(MaybeInherited::is_inherited),
+		pkg.keywords.as_ref().map(MaybeInherited::is_inherited),
+		pkg.categories.as_ref().map(MaybeInherited::is_inherited)
+	]
+	.contains(&Some(true))
+		|| manifest
+			.dependencies
+			.values()
+			.any(|dep| matches!(dep, ManifestDependency::Detailed(dep) if dep.workspace));
+	let mut workspace = inherits
+		.then(|| read_workspace(dir, pkg.workspace.as_deref()))
+		.transpose()?;
+	let mut ws_pkg = workspace.as_mut().map(|(_, ws)| &mut ws.package);
+
+	let version = inherit(pkg.version, "version", ws_pkg.as_deref_mut(), |ws| {
+		ws.version.take()
+	})?
+	.unwrap_or_else(|| "0.0.0".into());
+	let authors = inherit(pkg.authors, "authors", ws_pkg.as_deref_mut(), |ws| {
+		ws.authors.take()
+	})?
+	.unwrap_or_default();
+	let edition = inherit(pkg.edition, "edition", ws_pkg.as_deref_mut(), |ws| {
+		ws.edition.take()
+	})?
+	.unwrap_or_else(|| "2015".into());
+	let edition = edition.as_str();
+	let license = inherit(pkg.license, "license", ws_pkg.as_deref_mut(), |ws| {
+		ws.license.take()
+	})?;
+	let repository =
+		inherit(pkg.repository, "repository", ws_pkg.as_deref_mut(), |ws| {
+			ws.repository.take()
+		})?;
+	let rust_version = inherit(
+		pkg.rust_version,
+		"rust-version",
+		ws_pkg.as_deref_mut(),
+		|ws| ws.rust_version.take()
+	)?;
+	let keywords = inherit(pkg.keywords, "keywords", ws_pkg.as_deref_mut(), |ws| {
+		ws.keywords.take()
+	})?
+	.unwrap_or_default();
+	let categories = inherit(pkg.categories, "categories", ws_pkg, |ws| {
+		ws.categories.take()
+	})?
+	.unwrap_or_default();
+
+	let mut targets = Vec::new();
+	let lib_path = dir.join("src").join("lib.rs");
+	match manifest.lib {
+		Some(lib) => {
+			// like cargo, we report the crate types as the kind of library targets
+			let kind = if lib.proc_macro { "proc-macro" } else { "lib" };
+			let crate_types = lib.crate_type.unwrap_or_else(|| vec![kind.to_owned()]);
+			targets.push(target_json(
+				lib.name.unwrap_or_else(|| pkg.name.replace('-', "_")),
+				crate_types.clone(),
+				crate_types,
+				lib.path.map(|path| dir.join(path)).unwrap_or(lib_path),
+				edition
+			));
+		},
+		None if lib_path.exists() => {
+			targets.push(target_json(
+				pkg.name.replace('-', "_"),
+				vec!["lib".into()],
+				vec!["lib".into()],
+				lib_path,
+				edition
+			));
+		},
+		None => {}
+	}
+	let main_path = dir.join("src").join("main.rs");
+	if main_path.exists()
+		&& !manifest
+			.bin
+			.iter()
+			.any(|bin| bin.name.as_deref() == Some(&pkg.name))
+	{
+		targets.push(target_json(
+			pkg.name.clone(),
+			vec!["bin".into()],
+			vec!["bin".into()],
+			main_path,
+			edition
+		));
+	}
+	for bin in manifest.bin {
+		let name = bin.name.context("Binary target is missing a name")?;
+		let path = match bin.path {
+			Some(path) => dir.join(path),
+			None if name == pkg.name => dir.join("src").join("main.rs"),
+			None => dir.join("src").join("bin").join(format!("{name}.rs"))
+		};
+		targets.push(target_json(
+			name,
+			vec!["bin".into()],
+			vec!["bin".into()],
+			path,
+			edition
+		));
+	}
+	for example in manifest.example {
+		let name = example.name.context("Example target is missing a name")?;
+		let path = match example.path {
+			Some(path) => dir.join(path),
+			None => dir.join("examples").join(format!("{name}.rs"))
+		};
+		targets.push(target_json(
+			name,
+			vec!["example".into()],
+			vec!["bin".into()],
+			path,
+			edition
+		));
+	}
+
+	let dependencies = manifest
+		.dependencies
+		.into_iter()
+		.map(|(key, dep)| {
+			let mut dep = dep.into_detail();
+			if dep.workspace {
+				let (ws_dir, ws) =
+					workspace.as_mut().expect("workspace should have been read");
+				let ws_dep = ws
+					.dependencies
+					.remove(&key)
+					.with_context(|| {
+						format!("`{key}` was not defined in `workspace.dependencies`")
+					})?
+					.into_detail();
+				let mut features = ws_dep.features;
+				features.append(&mut dep.features);
+				dep = ManifestDependencyDetail {
+					version: ws_dep.version,
+					package: ws_dep.package,
+					path: ws_dep.path.map(|path| ws_dir.join(path)),
+					optional: dep.optional,
+					default_features: ws_dep.default_features,
+					features,
+					workspace: false
+				};
+			}
+			Ok((key, dep))
+		})
+		.collect::<anyhow::Result<Vec<_>>>()?;
+
+	// like cargo, every optional dependency that isn't referenced using `dep:` syntax
+	// has an implicit feature of the same name
+	let mut features = manifest.features;
+	for (key, dep) in &dependencies {
+		let dep_feature = format!("dep:{key}");
+		if dep.optional
+			&& !features
+				.values()
+				.flatten()
+				.any(|feature| *feature == dep_feature)
+		{
+			features
+				.entry(key.clone())
+				.or_insert_with(|| vec![dep_feature]);
+		}
+	}
+
+	let dependencies = dependencies
+		.into_iter()
+		.map(|(key, dep)| {
+			let (name, rename) = match dep.package {
+				Some(package) => (package, Some(key)),
+				None => (key, None)
+			};
+			json!({
+				"name": name,
+				"source": dep.path.is_none().then_some("registry+https://github.com/rust-lang/crates.io-index"),
+				"req": dep.version.as_deref().unwrap_or("*"),
+				"kind": null,
+				"optional": dep.optional,
+				"uses_default_features": dep.default_features.unwrap_or(true),
+				"features": dep.features,
+				"target": null,
+				"rename": rename,
+				"registry": null,
+				"path": dep.path.map(|path| dir.join(path))
+			})
+		})
+		.collect::<Vec<_>>();
+
+	let id = format!("path+file://{}#{}@{version}", dir.display(), pkg.name);
+	let metadata = json!({
+		"packages": [{
+			"name": pkg.name,
+			"version": version,
+			"authors": authors,
+			"id": id,
+			"source": null,
+			"description": null,
+			"dependencies": dependencies,
+			"license": license,
+			"license_file": null,
+			"targets": targets,
+			"features": features,
+			"manifest_path": manifest_path,
+			"readme": null,
+			"repository": repository,
+			"homepage": null,
+			"documentation": null,
+			"edition": edition,
+			"keywords": keywords,
+			"categories": categories,
+			"metadata": pkg.metadata,
+			"links": null,
+			"publish": null,
+			"default_run": null,
+			"rust_version": rust_version
+		}],
+		"workspace_members": [id],
+		"resolve": null,
+		"workspace_root": dir,
+		"target_directory": dir.join("target"),
+		"version": 1
+	});
+	Ok(MetadataCommand::parse(metadata.to_string())?)
+}
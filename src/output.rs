@@ -1,19 +1,29 @@
 use crate::{
-	input::{InputFile, Scope, TargetType},
-	links::Links
+	diagnostic::Diagnostic,
+	input::{BadgeStyle, InputFile, Scope, TargetType},
+	links::{Links, STD_CRATES},
+	render::{self, AsciiDoc, PlainText}
 };
+use anyhow::bail;
+use cargo_metadata::DependencyKind;
+use clap::ValueEnum;
 use itertools::Itertools as _;
 use log::debug;
+use memchr::memmem;
+use percent_encoding::percent_decode_str;
 use pulldown_cmark::{
 	BrokenLink, CodeBlockKind, CowStr, Event, HeadingLevel, LinkType, Options, Parser,
 	Tag, TagEnd
 };
-use semver::Version;
-use serde::Serialize;
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
 use std::{
-	collections::BTreeMap,
-	fmt::{self, Write as _},
-	io
+	borrow::Cow,
+	collections::{BTreeMap, BTreeSet, VecDeque},
+	fmt::{self, Display, Write as _},
+	io, mem,
+	ops::Range,
+	str::FromStr
 };
 use syn::Path;
 use url::Url;
@@ -26,14 +36,349 @@ const RUSTDOC_CODEBLOCK_FLAGS: &[&str] = &[
 	"edition2015",
 	"edition2018",
 	"edition2021",
+	"edition2024",
 	RUSTDOC_CODEBLOCK_IGNORE_FLAG,
 	"no_run",
 	"should_panic"
 ];
 
+/// The order in which the link definitions are emitted at the end of the readme.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum SortLinks {
+	/// Sort the link definitions by their reference name.
+	Name,
+	/// Sort the link definitions by their destination url.
+	Url
+}
+
+/// The style of the reference names of the links in the readme.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkStyle {
+	/// Use reference names like `__link0` that are unlikely to clash with anything.
+	#[default]
+	Reference,
+	/// Number the links in the order of their first appearance, like `[1]`.
+	Footnote
+}
+
+/// The marker of the items of unordered lists.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ListMarker {
+	/// Use `*` for list items.
+	#[default]
+	Asterisk,
+	/// Use `-` for list items.
+	Dash,
+	/// Use `+` for list items.
+	Plus
+}
+
+impl ListMarker {
+	fn token(self) -> char {
+		match self {
+			Self::Asterisk => '*',
+			Self::Dash => '-',
+			Self::Plus => '+'
+		}
+	}
+}
+
+/// The character that is used for emphasis and, doubled, for strong emphasis.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum EmphasisChar {
+	/// Use `*emphasis*` and `**strong**`.
+	#[default]
+	Asterisk,
+	/// Use `_emphasis_` and `__strong__`.
+	Underscore
+}
+
+impl EmphasisChar {
+	fn token(self) -> char {
+		match self {
+			Self::Asterisk => '*',
+			Self::Underscore => '_'
+		}
+	}
+
+	fn strong_token(self) -> &'static str {
+		match self {
+			Self::Asterisk => "**",
+			Self::Underscore => "__"
+		}
+	}
+}
+
+/// The style of thematic breaks.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ThematicBreak {
+	/// Use `---` for thematic breaks.
+	#[default]
+	Dash,
+	/// Use `***` for thematic breaks.
+	Asterisk,
+	/// Use `___` for thematic breaks.
+	Underscore
+}
+
+impl ThematicBreak {
+	fn token(self) -> &'static str {
+		match self {
+			Self::Dash => "---",
+			Self::Asterisk => "***",
+			Self::Underscore => "___"
+		}
+	}
+}
+
+/// What to do with the flags of code blocks that are meant for rustdoc, like
+/// `should_panic`.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CodeblockFlags {
+	/// Remove the flags from the info string.
+	#[default]
+	Strip,
+	/// Keep the flags in the info string, after the language.
+	Preserve,
+	/// Remove the flags from the info string, and add a comment with them as the first
+	/// line of rust code blocks.
+	Comment
+}
+
+/// The format of the generated readme.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+	/// Markdown, e.g. for GitHub and crates.io.
+	#[default]
+	Markdown,
+	/// AsciiDoc, with all links inlined.
+	Asciidoc,
+	/// Plain text without any formatting.
+	Text
+}
+
+impl OutputFormat {
+	/// Convert the markdown, including its link definitions, into this format.
+	fn render(self, markdown: &str) -> String {
+		match self {
+			Self::Markdown => markdown.to_owned(),
+			Self::Asciidoc => render::render(AsciiDoc::default(), markdown),
+			Self::Text => render::render(PlainText::default(), markdown)
+		}
+	}
+}
+
+/// Return the footnote label of a link with the reference name `__link{idx}`.
+fn footnote_label(name: &str) -> String {
+	let idx: usize = name["__link".len() ..]
+		.parse()
+		.expect("Invalid link reference name");
+	(idx + 1).to_string()
+}
+
+/// Options that alter the generated readme, but not the input that is being read.
+#[derive(Clone, Debug, Default)]
+pub struct EmitOptions {
+	/// Sort the link definitions. By default, they are emitted in the order of their
+	/// reference names as stored in a [`BTreeMap`].
+	pub sort_links: Option<SortLinks>,
+
+	/// Keep the level of the first heading in the rustdoc instead of increasing it.
+	pub keep_first_heading: bool,
+
+	/// Remove a level 1 heading at the start of the rustdoc if it is the crate name, the
+	/// library name or the configured title.
+	pub dedup_title: bool,
+
+	/// Link to the docs of workspace members relative to this base instead of docs.rs.
+	pub local_docs_base: Option<String>,
+
+	/// The base url for links to crates, instead of `https://crates.io`.
+	pub crates_io_base_url: Option<String>,
+
+	/// The base url for links to the docs of crates, instead of `https://docs.rs`.
+	pub docs_rs_base_url: Option<String>,
+
+	/// The base url for links to the standard library, instead of
+	/// `https://doc.rust-lang.org/stable`.
+	pub std_docs_base_url: Option<String>,
+
+	/// Link to the stable standard library even if the crate has a `rust-version`.
+	pub std_docs_stable: bool,
+
+	/// The format of the `title` that is passed to the template.
+	pub title_format: Option<TitleFormat>,
+
+	/// Link to unknown items of a type using the search instead of guessing that they
+	/// are methods or enum variants.
+	pub no_method_anchors: bool,
+
+	/// Write only the readme and the links without using the template.
+	pub bare: bool,
+
+	/// Write only the link definitions, including the dependency info.
+	pub links_only: bool,
+
+	/// Keep the lines of code blocks that rustdoc hides, without their `# ` marker.
+	pub show_hidden_lines: bool,
+
+	/// Append a comment with the hash of the generated readme.
+	pub emit_content_hash: bool,
+
+	/// The style of the reference names of the links.
+	pub link_style: LinkStyle,
+
+	/// The marker of the items of unordered lists.
+	pub list_marker: ListMarker,
+
+	/// The character used for emphasis.
+	pub emphasis_char: EmphasisChar,
+
+	/// The style of thematic breaks.
+	pub thematic_break: ThematicBreak,
+
+	/// What to do with the flags of code blocks that are meant for rustdoc.
+	pub codeblock_flags: CodeblockFlags,
+
+	/// Wrap the readme in a fenced code block with this language, so that the markdown
+	/// is shown as-is instead of being rendered.
+	pub wrap_in_fence: Option<String>,
+
+	/// The format of the readme. Anything other than markdown has its links inlined and
+	/// cannot contain the dependency info.
+	pub output_format: OutputFormat
+}
+
+impl EmitOptions {
+	/// Return the template that is used with these options. No template is used in bare
+	/// mode, which is represented by an empty template.
+	pub(crate) fn template<'t>(&self, template: &'t str) -> &'t str {
+		if self.bare {
+			""
+		} else {
+			template
+		}
+	}
+
+	/// Return the template as it is hashed in the dependency info. All options that affect
+	/// the output and differ from their default are appended, so that changing them
+	/// outdates the readme, while readmes that use the default options are unaffected.
+	pub(crate) fn hashed_template<'t>(&self, template: &'t str) -> Cow<'t, str> {
+		fn variant<T: ValueEnum>(value: &T) -> String {
+			value.to_possible_value().unwrap().get_name().to_owned()
+		}
+
+		fn style<T: Default + PartialEq + ValueEnum>(
+			name: &str,
+			value: T
+		) -> Option<String> {
+			(value != T::default()).then(|| format!("\0{name}={}", variant(&value)))
+		}
+
+		fn flag(name: &str, value: bool) -> Option<String> {
+			value.then(|| format!("\0{name}"))
+		}
+
+		fn value<T: Display>(name: &str, value: Option<T>) -> Option<String> {
+			value.map(|value| format!("\0{name}={value}"))
+		}
+
+		let styles = [
+			style("list-marker", self.list_marker),
+			style("emphasis-char", self.emphasis_char),
+			style("thematic-break", self.thematic_break),
+			style("codeblock-flags", self.codeblock_flags),
+			style("output-format", self.output_format),
+			self.links_only.then(|| "\0links-only".to_owned()),
+			self.show_hidden_lines
+				.then(|| "\0show-hidden-lines".to_owned()),
+			self.emit_content_hash
+				.then(|| "\0emit-content-hash".to_owned()),
+			self.wrap_in_fence
+				.as_ref()
+				.map(|lang| format!("\0wrap-in-fence={lang}")),
+			style("link-style", self.link_style),
+			value("sort-links", self.sort_links.as_ref().map(variant)),
+			value(
+				"title-format",
+				self.title_format.as_ref().map(|format| &format.0)
+			),
+			flag("keep-first-heading", self.keep_first_heading),
+			flag("dedup-title", self.dedup_title),
+			flag("bare", self.bare),
+			value("crates-io-base-url", self.crates_io_base_url.as_deref()),
+			value("docs-rs-base-url", self.docs_rs_base_url.as_deref()),
+			value("std-docs-base-url", self.std_docs_base_url.as_deref()),
+			flag("std-docs-stable", self.std_docs_stable),
+			flag("no-method-anchors", self.no_method_anchors),
+			value("local-docs-base", self.local_docs_base.as_deref())
+		];
+		if styles.iter().all(Option::is_none) {
+			return Cow::Borrowed(template);
+		}
+		Cow::Owned(format!(
+			"{template}{}",
+			styles.into_iter().flatten().join("")
+		))
+	}
+}
+
+/// The placeholders that can be used in a [`TitleFormat`].
+const TITLE_PLACEHOLDERS: &[&str] = &["crate", "version", "title"];
+
+/// A format for the title of the readme, like `{crate} v{version}`. The placeholders
+/// `{crate}`, `{version}` and `{title}` are replaced by the crate name, the crate version
+/// and the title from the package metadata, which defaults to the crate name.
+#[derive(Clone, Debug)]
+pub struct TitleFormat(String);
+
+impl FromStr for TitleFormat {
+	type Err = anyhow::Error;
+
+	fn from_str(format: &str) -> anyhow::Result<Self> {
+		let mut rest = format;
+		while let Some(start) = rest.find('{') {
+			let Some(len) = rest[start ..].find('}') else {
+				bail!("Unclosed placeholder in title format `{format}`");
+			};
+			let placeholder = &rest[start + 1 .. start + len];
+			if !TITLE_PLACEHOLDERS.contains(&placeholder) {
+				bail!(
+					"Unknown placeholder `{{{placeholder}}}` in title format, expected one of {}",
+					TITLE_PLACEHOLDERS
+						.iter()
+						.map(|placeholder| format!("`{{{placeholder}}}`"))
+						.join(", ")
+				);
+			}
+			rest = &rest[start + len + 1 ..];
+		}
+		Ok(Self(format.to_owned()))
+	}
+}
+
+impl TitleFormat {
+	fn format(&self, krate: &str, version: &Version, title: &str) -> String {
+		// the title is replaced last as it is the only one that might contain braces
+		self.0
+			.replace("{crate}", krate)
+			.replace("{version}", &version.to_string())
+			.replace("{title}", title)
+	}
+}
+
 pub struct ResolvedLink {
 	pub path: String,
-	pub link_type: Option<crate::input::LinkType>
+	pub link_type: Option<crate::input::LinkType>,
+	/// The type of the item that contains the linked item, if known.
+	pub parent_link_type: Option<crate::input::LinkType>
 }
 
 impl Scope {
@@ -45,86 +390,254 @@ impl Scope {
 		&self,
 		crate_name: &str,
 		link_type: Option<crate::input::LinkType>,
-		path: String
+		mut path: String
 	) -> ResolvedLink {
-		if !path.starts_with("::") {
-			// split path into segments, ignoring <...> generics
-			let mut path = path.clone();
-			loop {
-				let idx = match (path.find('<'), path.rfind('>')) {
-					(Some(idx1), Some(idx2)) if idx1 < idx2 => idx1,
-					_ => break
-				};
-				let mut end = idx + 1;
-				let mut depth: usize = 1;
-				for ch in path[end ..].chars() {
-					if ch == '<' {
-						depth += 1;
-					} else if ch == '>' {
-						depth -= 1;
-					}
-					end += ch.len_utf8();
+		// crate names may contain hyphens, but they are replaced by underscores in rust code
+		let first_len = path.find("::").unwrap_or(path.len());
+		if path[.. first_len].contains('-') {
+			path = path[.. first_len].replace('-', "_") + &path[first_len ..];
+		}
 
-					if depth == 0 {
-						break;
-					}
+		// a leading `::` means that the first segment is a crate, which must not be
+		// resolved through the scope
+		let absolute = path.starts_with("::");
+
+		// split path into segments, ignoring <...> generics
+		let mut stripped = path.trim_start_matches("::").to_owned();
+		loop {
+			let idx = match (stripped.find('<'), stripped.rfind('>')) {
+				(Some(idx1), Some(idx2)) if idx1 < idx2 => idx1,
+				_ => break
+			};
+			let mut end = idx + 1;
+			let mut depth: usize = 1;
+			for ch in stripped[end ..].chars() {
+				if ch == '<' {
+					depth += 1;
+				} else if ch == '>' {
+					depth -= 1;
+				}
+				end += ch.len_utf8();
+
+				if depth == 0 {
+					break;
 				}
-				path.replace_range(idx .. end, "");
-			}
-			debug!("Resolving path {path:?}");
-			let mut segments = path.split("::").collect::<Vec<_>>();
-			if segments[0] == "crate" {
-				segments[0] = crate_name;
 			}
+			stripped.replace_range(idx .. end, "");
+		}
+		debug!("Resolving path {stripped:?}");
+		let mut segments = stripped.split("::").collect::<Vec<_>>();
+		// the crate documentation is the documentation of the root module, so `self`
+		// refers to the crate as well
+		if segments[0] == "crate" || (segments[0] == "self" && !absolute) {
+			segments[0] = crate_name;
+		}
 
-			// check if we can resolve anything
-			if self.scope.contains_key(segments[0]) {
-				let paths = &self.scope[segments[0]];
-				if let Some((path_link_type, path)) = paths.front() {
-					segments[0] = path;
-					let path = segments.join("::");
-					if path.starts_with("::") {
-						return ResolvedLink {
-							path,
-							link_type: if segments.len() == 1 {
-								Some(*path_link_type)
-							} else {
-								link_type
-							}
-						};
-					}
-					return self.resolve(crate_name, segments.join("::"));
+		// check if we can resolve anything
+		if !absolute && self.scope.contains_key(segments[0]) {
+			let paths = &self.scope[segments[0]];
+			if let Some((path_link_type, path)) = paths.front() {
+				segments[0] = path;
+				let path = segments.join("::");
+				if path.starts_with("::") {
+					let link_type = if segments.len() == 1 {
+						Some(*path_link_type)
+					} else {
+						link_type.or_else(|| self.items.get(&path).copied())
+					};
+					let parent_link_type = match segments.len() {
+						1 => None,
+						2 => Some(*path_link_type),
+						len => self.items.get(&segments[.. len - 1].join("::")).copied()
+					};
+					return ResolvedLink {
+						path,
+						link_type,
+						parent_link_type
+					};
 				}
+				return self.resolve(crate_name, segments.join("::"));
 			}
 		}
 
-		ResolvedLink { path, link_type }
+		// check if this is the full path of an item we know, like
+		// `proc_macro::TokenStream`
+		if link_type.is_none() && segments.len() > 1 {
+			let path = format!("::{}", segments.join("::"));
+			let known = self
+				.scope
+				.get(segments[segments.len() - 1])
+				.and_then(|paths| paths.iter().find(|(_, known)| *known == path))
+				.map(|(known_link_type, _)| known_link_type)
+				.or_else(|| self.items.get(&path));
+			if let Some(known_link_type) = known {
+				return ResolvedLink {
+					path,
+					link_type: Some(*known_link_type),
+					parent_link_type: None
+				};
+			}
+		}
+
+		ResolvedLink {
+			path,
+			link_type,
+			parent_link_type: None
+		}
 	}
 }
 
-fn broken_link_callback<'a>(lnk: BrokenLink<'_>) -> Option<(CowStr<'a>, CowStr<'a>)> {
+fn broken_link_callback<'a>(
+	lnk: BrokenLink<'_>,
+	ignore_links: &[String]
+) -> Option<(CowStr<'a>, CowStr<'a>)> {
+	// ignored names, and paths starting with them, are not turned into links
+	let reference = lnk.reference.trim_matches('`');
+	if ignore_links.iter().any(|name| {
+		reference == name
+			|| reference
+				.strip_prefix(name.as_str())
+				.map_or(false, |rest| rest.starts_with("::"))
+	}) {
+		return None;
+	}
 	Some(("".into(), lnk.reference.to_string().into()))
 }
 
+/// Return true if the markdown contains no text, e.g. because it only contains code
+/// blocks whose lines are all hidden.
+fn is_empty_markdown(markdown: &str) -> bool {
+	Parser::new_ext(markdown, Options::all()).all(|event| match event {
+		Event::Start(Tag::Image { .. }) => false,
+		Event::Start(_) | Event::End(_) => true,
+		Event::Text(text) | Event::Code(text) => text.trim().is_empty(),
+		_ => false
+	})
+}
+
 fn is_hidden_codeblock_line(line: &str) -> bool {
 	line == "#"
 		|| (line.starts_with('#') && line.chars().nth(1).unwrap_or('a').is_whitespace())
 }
 
-struct EventFilter<'a, I: Iterator<Item = Event<'a>>> {
+/// Remove the marker from a line that rustdoc hides, so that it is shown as it is
+/// compiled.
+fn unhide_codeblock_line(line: &str) -> &str {
+	match line.strip_prefix('#') {
+		Some(rest) if is_hidden_codeblock_line(line) => {
+			rest.strip_prefix(char::is_whitespace).unwrap_or(rest)
+		},
+		_ => line
+	}
+}
+
+/// Normalize the fragment of an in-document link to the anchor that GitHub creates for
+/// the heading with that text: Lowercase, spaces are replaced by hyphens, and all
+/// punctuation except for hyphens and underscores is removed.
+fn github_slug(text: &str) -> String {
+	text.trim()
+		.chars()
+		.filter_map(|ch| match ch {
+			' ' => Some('-'),
+			'-' | '_' => Some(ch),
+			ch if ch.is_alphanumeric() => Some(ch),
+			_ => None
+		})
+		.flat_map(char::to_lowercase)
+		.collect()
+}
+
+/// Return the text and the anchor that GitHub creates for every heading in the markdown.
+fn heading_anchors(markdown: &str) -> Vec<(String, String)> {
+	let mut anchors = Vec::new();
+	// github appends a counter to the anchors of headings with the same text
+	let mut counts = BTreeMap::<String, usize>::new();
+	let mut heading = None;
+	for event in Parser::new_ext(markdown, Options::all()) {
+		match event {
+			Event::Start(Tag::Heading { .. }) => heading = Some(String::new()),
+			Event::Text(text) | Event::Code(text) => {
+				if let Some(heading) = &mut heading {
+					*heading += &text;
+				}
+			},
+			Event::End(TagEnd::Heading(_)) => {
+				let Some(heading) = heading.take() else {
+					continue;
+				};
+				let slug = github_slug(&heading);
+				let count = counts.entry(slug.clone()).or_default();
+				let anchor = match *count {
+					0 => slug,
+					count => format!("{slug}-{count}")
+				};
+				*count += 1;
+				anchors.push((heading, anchor));
+			},
+			_ => {}
+		}
+	}
+	anchors
+}
+
+struct EventFilter<'a, I: Iterator<Item = (Event<'a>, Range<usize>)>> {
 	iter: I,
 	links: &'a mut BTreeMap<String, String>,
+	/// The names of the links that point to images and must not be resolved as paths.
+	images: &'a mut BTreeSet<String>,
+	/// The titles of the links that have one.
+	titles: &'a mut BTreeMap<String, String>,
+	/// Additional codeblock flags that should be removed from the fence language.
+	codeblock_flags: &'a [String],
+	/// Whether the next heading should keep its level.
+	keep_heading: bool,
+	/// The style of thematic breaks.
+	thematic_break: ThematicBreak,
+	/// What to do with the flags of code blocks.
+	flags_policy: CodeblockFlags,
+	/// Whether hidden lines of code blocks are kept.
+	show_hidden_lines: bool,
+	/// If the rustdoc starts with a level 1 heading with one of these texts, it is
+	/// removed as it duplicates the title of the template.
+	duplicate_titles: Vec<&'a str>,
+	/// Events that were read from `iter` but not yet processed.
+	pending: VecDeque<(Event<'a>, Range<usize>)>,
+	/// The range in the rustdoc of every link.
+	link_ranges: BTreeMap<String, Range<usize>>,
+	/// The anchors of the headings in the rustdoc. Only links to these are normalized.
+	heading_anchors: BTreeSet<String>,
 
 	in_code_block: bool,
 	in_code_block_ignored: bool,
 	link_idx: usize
 }
 
-impl<'a, I: Iterator<Item = Event<'a>>> EventFilter<'a, I> {
-	fn new(iter: I, links: &'a mut BTreeMap<String, String>) -> Self {
+impl<'a, I: Iterator<Item = (Event<'a>, Range<usize>)>> EventFilter<'a, I> {
+	#[allow(clippy::too_many_arguments)]
+	fn new(
+		iter: I,
+		links: &'a mut BTreeMap<String, String>,
+		images: &'a mut BTreeSet<String>,
+		titles: &'a mut BTreeMap<String, String>,
+		codeblock_flags: &'a [String],
+		options: &EmitOptions,
+		duplicate_titles: Vec<&'a str>,
+		heading_anchors: BTreeSet<String>
+	) -> Self {
 		Self {
 			iter,
 			links,
+			images,
+			titles,
+			codeblock_flags,
+			keep_heading: options.keep_first_heading,
+			thematic_break: options.thematic_break,
+			flags_policy: options.codeblock_flags,
+			show_hidden_lines: options.show_hidden_lines,
+			duplicate_titles,
+			pending: VecDeque::new(),
+			link_ranges: BTreeMap::new(),
+			heading_anchors,
 
 			in_code_block: false,
 			in_code_block_ignored: false,
@@ -133,14 +646,62 @@ impl<'a, I: Iterator<Item = Event<'a>>> EventFilter<'a, I> {
 	}
 }
 
-impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
+impl<'a, I: Iterator<Item = (Event<'a>, Range<usize>)>> EventFilter<'a, I> {
+	fn next_event(&mut self) -> Option<(Event<'a>, Range<usize>)> {
+		if !self.duplicate_titles.is_empty() {
+			self.skip_duplicate_title();
+		}
+		self.pending.pop_front().or_else(|| self.iter.next())
+	}
+
+	/// Skip the first heading if it is a level 1 heading that duplicates the title.
+	fn skip_duplicate_title(&mut self) {
+		let titles = mem::take(&mut self.duplicate_titles);
+		match self.iter.next() {
+			Some((
+				event @ Event::Start(Tag::Heading {
+					level: HeadingLevel::H1,
+					..
+				}),
+				range
+			)) => {
+				self.pending.push_back((event, range));
+			},
+			Some(event) => {
+				self.pending.push_back(event);
+				return;
+			},
+			None => return
+		}
+
+		let mut text = String::new();
+		for (event, range) in self.iter.by_ref() {
+			match &event {
+				Event::Text(str) | Event::Code(str) => text += str,
+				_ => {}
+			}
+			let end = matches!(event, Event::End(TagEnd::Heading(_)));
+			self.pending.push_back((event, range));
+			if end {
+				break;
+			}
+		}
+		if titles.contains(&text.trim()) {
+			self.pending.clear();
+		}
+	}
+}
+
+impl<'a, I: Iterator<Item = (Event<'a>, Range<usize>)>> Iterator for EventFilter<'a, I> {
 	type Item = Event<'a>;
 
 	fn next(&mut self) -> Option<Self::Item> {
 		loop {
-			break Some(match self.iter.next()? {
+			let (event, range) = self.next_event()?;
+			break Some(match event {
 				Event::Start(tag) => Event::Start(match tag {
-					// we increase headings by 1 level
+					// we increase headings by 1 level, unless the first heading should
+					// be kept as-is
 					Tag::Heading {
 						level,
 						id,
@@ -148,6 +709,7 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 						attrs
 					} => {
 						let level = match level {
+							_ if mem::take(&mut self.keep_heading) => level,
 							HeadingLevel::H1 => HeadingLevel::H2,
 							HeadingLevel::H2 => HeadingLevel::H3,
 							HeadingLevel::H3 => HeadingLevel::H4,
@@ -172,28 +734,76 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 						Tag::CodeBlock(CodeBlockKind::Fenced(match kind {
 							CodeBlockKind::Indented => DEFAULT_CODEBLOCK_LANG.into(),
 							CodeBlockKind::Fenced(lang) => {
-								let mut lang: String = (*lang).to_owned();
+								// the info string is a list of tokens, and only tokens that
+								// are exactly a flag are removed
+								let tokens = lang
+									.split(|ch: char| ch == ',' || ch.is_whitespace())
+									.filter(|token| !token.is_empty())
+									.collect::<Vec<_>>();
 								self.in_code_block_ignored =
-									lang.contains(RUSTDOC_CODEBLOCK_IGNORE_FLAG);
-								for flag in RUSTDOC_CODEBLOCK_FLAGS {
-									lang = lang.replace(flag, "");
+									tokens.contains(&RUSTDOC_CODEBLOCK_IGNORE_FLAG);
+								let (flags, mut tokens): (Vec<_>, Vec<_>) =
+									tokens.into_iter().partition(|token| {
+										RUSTDOC_CODEBLOCK_FLAGS.contains(token)
+											|| self
+												.codeblock_flags
+												.iter()
+												.any(|flag| flag == token)
+									});
+								if tokens.is_empty() {
+									tokens.push(DEFAULT_CODEBLOCK_LANG);
 								}
-								let mut lang: CowStr<'_> = lang.replace(',', "").into();
-								if lang.is_empty() {
-									lang = DEFAULT_CODEBLOCK_LANG.into();
+								match self.flags_policy {
+									CodeblockFlags::Strip => {},
+									CodeblockFlags::Preserve => tokens.extend(flags),
+									// only rust code can contain the flags as a comment
+									CodeblockFlags::Comment
+										if tokens == [DEFAULT_CODEBLOCK_LANG]
+											&& !flags.is_empty() =>
+									{
+										self.pending.push_back((
+											Event::Text(
+												format!("// {}\n", flags.join(", "))
+													.into()
+											),
+											range
+										));
+									},
+									CodeblockFlags::Comment => {}
 								}
-								lang
+								tokens.join(",").into()
 							}
 						}))
 					},
 
+					// links to headings are normalized to the anchor that GitHub
+					// creates, but other anchors are kept as-is
+					Tag::Link {
+						link_type,
+						dest_url,
+						title,
+						id
+					} if dest_url.starts_with('#') => {
+						let fragment =
+							percent_decode_str(&dest_url[1 ..]).decode_utf8_lossy();
+						let slug = github_slug(&fragment);
+						Tag::Link {
+							link_type,
+							dest_url: if self.heading_anchors.contains(&slug) {
+								format!("#{slug}").into()
+							} else {
+								dest_url
+							},
+							title,
+							id
+						}
+					},
 					Tag::Link {
 						link_type,
 						dest_url,
 						title,
 						id
-					} if dest_url.starts_with('#')
-						|| link_type == LinkType::Autolink
+					} if link_type == LinkType::Autolink
 						|| link_type == LinkType::Email =>
 					{
 						Tag::Link {
@@ -211,8 +821,14 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 					} => {
 						let link = format!("__link{}", self.link_idx);
 						self.link_idx += 1;
+						self.link_ranges.insert(link.clone(), range);
 						if !dest_url.is_empty() {
 							self.links.insert(link.clone(), dest_url.to_string());
+							// the title of broken links is used by the broken link
+							// callback, so we only keep it for links with a destination
+							if !title.is_empty() {
+								self.titles.insert(link.clone(), title.to_string());
+							}
 						} else if !id.is_empty() {
 							self.links.insert(link.clone(), id.to_string());
 						} else if !title.is_empty() {
@@ -236,6 +852,30 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 						}
 					},
 
+					// reference-style images are emitted as references, but their
+					// destination is never resolved as a path
+					Tag::Image {
+						link_type:
+							LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut,
+						dest_url,
+						title,
+						id
+					} if !dest_url.is_empty() => {
+						let link = format!("__link{}", self.link_idx);
+						self.link_idx += 1;
+						self.links.insert(link.clone(), dest_url.to_string());
+						self.images.insert(link.clone());
+						if !title.is_empty() {
+							self.titles.insert(link.clone(), title.to_string());
+						}
+						Tag::Image {
+							link_type: LinkType::Inline,
+							dest_url: link.into(),
+							title: "".into(),
+							id
+						}
+					},
+
 					// we don't need to modify any other tags
 					tag => tag
 				}),
@@ -255,6 +895,17 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 					tag => tag
 				}),
 
+				Event::Text(text)
+					if self.in_code_block
+						&& !self.in_code_block_ignored
+						&& self.show_hidden_lines =>
+				{
+					let mut unhidden = text.lines().map(unhide_codeblock_line).join("\n");
+					if text.ends_with('\n') {
+						unhidden.push('\n');
+					}
+					Event::Text(unhidden.into())
+				},
 				Event::Text(text)
 					if self.in_code_block && !self.in_code_block_ignored =>
 				{
@@ -271,6 +922,25 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 					Event::Text(filtered.into())
 				},
 
+				// the emitter writes hard breaks as two trailing spaces, which are easily
+				// lost, so we write a backslash followed by a line break instead
+				Event::HardBreak => {
+					self.pending.push_back((Event::SoftBreak, range));
+					Event::InlineHtml("\\".into())
+				},
+
+				// the emitter always uses `---` for thematic breaks, so other styles
+				// are written as raw html blocks
+				Event::Rule if self.thematic_break != ThematicBreak::Dash => {
+					self.pending.push_back((
+						Event::Html(format!("{}\n", self.thematic_break.token()).into()),
+						range.clone()
+					));
+					self.pending
+						.push_back((Event::End(TagEnd::HtmlBlock), range));
+					Event::Start(Tag::HtmlBlock)
+				},
+
 				ev => ev
 			});
 		}
@@ -280,6 +950,7 @@ impl<'a, I: Iterator<Item = Event<'a>>> Iterator for EventFilter<'a, I> {
 struct Readme<'a> {
 	template: &'a str,
 	input: &'a InputFile,
+	options: &'a EmitOptions,
 
 	/// Holds the main markdown part of the readme that was created from the rustdoc,
 	/// but does not include any parts of the template or the links.
@@ -288,38 +959,89 @@ struct Readme<'a> {
 	/// Holds the link part of the markdown.
 	readme_links: String,
 
-	links: BTreeMap<String, String>
+	/// Holds the encoded dependency info if it is not part of the readme links.
+	dep_info: Option<String>,
+
+	links: BTreeMap<String, String>,
+
+	/// The names of the links that point to images.
+	images: BTreeSet<String>,
+
+	/// The titles of the links that have one.
+	titles: BTreeMap<String, String>,
+
+	/// The range in the rustdoc of every link.
+	link_ranges: BTreeMap<String, Range<usize>>
 }
 
 impl<'a> Readme<'a> {
-	fn new(template: &'a str, input: &'a InputFile) -> Self {
+	fn new(template: &'a str, input: &'a InputFile, options: &'a EmitOptions) -> Self {
 		Self {
 			template,
 			input,
+			options,
 			readme: String::new(),
 			readme_links: String::new(),
-			links: BTreeMap::new()
+			dep_info: None,
+			links: BTreeMap::new(),
+			images: BTreeSet::new(),
+			titles: BTreeMap::new(),
+			link_ranges: BTreeMap::new()
 		}
 	}
 
 	fn write_markdown(&mut self) -> fmt::Result {
 		// we need this broken link callback for the purpose of broken links being parsed as links
-		let mut broken_link_callback = broken_link_callback;
+		let ignore_links = &self.input.metadata.ignore_links;
+		let mut broken_link_callback = |lnk| broken_link_callback(lnk, ignore_links);
 		let parser = Parser::new_with_broken_link_callback(
 			&self.input.rustdoc,
 			Options::all(),
 			Some(&mut broken_link_callback)
 		);
 
+		// code blocks that contain fences themselves need a longer fence
+		let code_block_token_count =
+			pulldown_cmark_to_cmark::calculate_code_block_token_count(Parser::new_ext(
+				&self.input.rustdoc,
+				Options::all()
+			))
+			.unwrap_or(3);
 		let options = pulldown_cmark_to_cmark::Options {
-			code_block_token_count: 3,
+			code_block_token_count,
+			list_token: self.options.list_marker.token(),
+			emphasis_token: self.options.emphasis_char.token(),
+			strong_token: self.options.emphasis_char.strong_token(),
 			..Default::default()
 		};
+		let mut filter = EventFilter::new(
+			parser.into_offset_iter(),
+			&mut self.links,
+			&mut self.images,
+			&mut self.titles,
+			&self.input.metadata.codeblock_flags,
+			self.options,
+			if self.options.dedup_title {
+				let title = self.input.metadata.title.as_deref();
+				let crate_name = self.input.crate_name.as_str();
+				[Some(crate_name), Some(self.input.lib_name.as_str()), title]
+					.into_iter()
+					.flatten()
+					.collect()
+			} else {
+				Vec::new()
+			},
+			heading_anchors(&self.input.rustdoc)
+				.into_iter()
+				.map(|(_, anchor)| anchor)
+				.collect()
+		);
 		pulldown_cmark_to_cmark::cmark_with_options(
-			EventFilter::new(parser.into_iter(), &mut self.links),
+			&mut filter,
 			&mut self.readme,
 			options
 		)?;
+		self.link_ranges = filter.link_ranges;
 
 		// we need to replace the links generated by pulldown-cmark-to-cmark with
 		// reference-style links
@@ -335,8 +1057,33 @@ impl<'a> Readme<'a> {
 			let idx2 = idx2 + idx;
 			i = idx2;
 
-			self.readme.replace_range(idx ..= idx, "[");
-			self.readme.replace_range(idx2 ..= idx2, "]");
+			if self.options.link_style == LinkStyle::Footnote {
+				let label = footnote_label(&self.readme[idx + 1 .. idx2]);
+				i = idx + label.len() + 2;
+				self.readme
+					.replace_range(idx ..= idx2, &format!("[{label}]"));
+			} else {
+				self.readme.replace_range(idx ..= idx, "[");
+				self.readme.replace_range(idx2 ..= idx2, "]");
+			}
+		}
+		if self.options.link_style == LinkStyle::Footnote {
+			self.links = mem::take(&mut self.links)
+				.into_iter()
+				.map(|(name, href)| (footnote_label(&name), href))
+				.collect();
+			self.images = mem::take(&mut self.images)
+				.iter()
+				.map(|name| footnote_label(name))
+				.collect();
+			self.titles = mem::take(&mut self.titles)
+				.into_iter()
+				.map(|(name, title)| (footnote_label(&name), title))
+				.collect();
+			self.link_ranges = mem::take(&mut self.link_ranges)
+				.into_iter()
+				.map(|(name, range)| (footnote_label(&name), range))
+				.collect();
 		}
 
 		if !self.readme.ends_with('\n') {
@@ -346,22 +1093,64 @@ impl<'a> Readme<'a> {
 		Ok(())
 	}
 
-	fn write_links(&mut self) {
-		let mut links = Links::new(self.template, &self.input.rustdoc);
-		for link in self.links.keys().map(|l| l.to_owned()).collect::<Vec<_>>() {
+	fn write_links(&mut self, dep_info_sidecar: bool, diagnostics: &mut Diagnostic) {
+		let mut links = Links::new(
+			&self.options.hashed_template(self.template),
+			&self.input.rustdoc
+		);
+		links.local_docs_base = self.options.local_docs_base.clone();
+		links.crates_io_base_url = self.options.crates_io_base_url.clone();
+		links.docs_rs_base_url = self.options.docs_rs_base_url.clone();
+		links.std_docs_base_url = self.options.std_docs_base_url.clone();
+		links.std_docs_stable = self.options.std_docs_stable;
+		links.no_method_anchors = self.options.no_method_anchors;
+		for link in self
+			.links
+			.keys()
+			.filter(|l| !self.images.contains(*l))
+			.map(|l| l.to_owned())
+			.collect::<Vec<_>>()
+		{
 			let mut href = self.links[&link].to_owned();
 			if href.starts_with('`') && href.ends_with('`') {
 				href = href[1 .. href.len() - 1].to_owned();
 			}
-			let href = self.input.scope.resolve(&self.input.crate_name, href);
+			let href = self.input.scope.resolve(&self.input.lib_name, href);
 
 			if let Ok(path) = syn::parse_str::<Path>(&href.path) {
-				self.links
-					.insert(link, links.build_link(&path, href.link_type, self.input));
+				if href.link_type.is_none() {
+					let first = path
+						.segments
+						.first()
+						.map(|segment| segment.ident.to_string())
+						.unwrap_or_default();
+					let known = first == "crate"
+						|| first == "self" || first == self.input.lib_name
+						|| STD_CRATES.contains(&first.as_str())
+						|| self.input.dependencies.contains_key(&first);
+					if !known {
+						let span = self.link_ranges.get(&link).and_then(|range| {
+							self.input.rustdoc_spans.find(range.clone())
+						});
+						diagnostics.warn_broken_link(&href.path, &first, span);
+					}
+				}
+				self.links.insert(
+					link,
+					links.build_link(
+						&path,
+						href.link_type,
+						href.parent_link_type,
+						self.input,
+						diagnostics
+					)
+				);
 			}
 		}
 
-		if !links.deps.is_empty() {
+		if dep_info_sidecar {
+			self.dep_info = Some(links.deps.encode());
+		} else if !links.deps.is_empty() {
 			writeln!(
 				self.readme_links,
 				" [__cargo_doc2readme_dependencies_info]: {}",
@@ -369,13 +1158,153 @@ impl<'a> Readme<'a> {
 			)
 			.unwrap();
 		}
-		for (name, href) in &self.links {
+		// renderers use the first definition of a reference, so a definition with the
+		// same name in the template breaks the link of the readme or of the template
+		let template = Parser::new_ext(self.template, Options::all());
+		let template_refs = template.reference_definitions();
+		for name in self.links.keys() {
+			if template_refs.get(name).is_some() {
+				diagnostics.warn(format!(
+					"The template defines the link reference `[{name}]` that is also used by a link from the rustdoc"
+				));
+			}
+		}
+
+		let mut links = self.links.iter().collect::<Vec<_>>();
+		// footnotes are always sorted by their number unless sorted by url
+		let sort_links = match self.options.link_style {
+			LinkStyle::Footnote => self.options.sort_links.or(Some(SortLinks::Name)),
+			LinkStyle::Reference => self.options.sort_links
+		};
+		match sort_links {
+			// reference names are __link0, __link1, ... so we sort them by length first
+			Some(SortLinks::Name) => links.sort_by(|(lhs, _), (rhs, _)| {
+				lhs.len().cmp(&rhs.len()).then_with(|| lhs.cmp(rhs))
+			}),
+			Some(SortLinks::Url) => links.sort_by(|(lhs_name, lhs), (rhs_name, rhs)| {
+				lhs.cmp(rhs).then_with(|| lhs_name.cmp(rhs_name))
+			}),
+			None => {}
+		}
+		for (name, href) in links {
 			// unwrap: writing to a String never fails
-			writeln!(self.readme_links, " [{}]: {}", name, href).unwrap();
+			match self.titles.get(name) {
+				Some(title) => writeln!(
+					self.readme_links,
+					" [{}]: {} \"{}\"",
+					name,
+					href,
+					title.replace('"', "\\\"")
+				),
+				None => writeln!(self.readme_links, " [{}]: {}", name, href)
+			}
+			.unwrap();
 		}
 	}
 }
 
+/// The JSON representation of the input for `--dump-input`. This is not a stable format.
+#[derive(Serialize)]
+struct InputDump<'a> {
+	#[serde(rename = "crate")]
+	krate: &'a str,
+	#[serde(rename = "crate_version")]
+	krate_version: &'a Version,
+	lib_name: &'a str,
+	target: TargetType,
+	dependencies: BTreeMap<&'a str, DependencyDump<'a>>,
+	links: Vec<LinkDump>
+}
+
+#[derive(Serialize)]
+struct DependencyDump<'a> {
+	#[serde(rename = "crate")]
+	krate: &'a str,
+	lib_name: &'a str,
+	req: &'a VersionReq,
+	version: &'a Version
+}
+
+#[derive(Serialize)]
+struct LinkDump {
+	/// The link destination as it appears in the rustdoc.
+	href: String,
+	/// The url that the link resolved to.
+	url: String
+}
+
+/// Write the input and the links it contains as JSON to `out_file`, without emitting
+/// the readme.
+pub fn dump_input(
+	input: &InputFile,
+	template: &str,
+	options: &EmitOptions,
+	out_file: &mut dyn io::Write,
+	diagnostics: &mut Diagnostic
+) -> anyhow::Result<()> {
+	let template = options.template(template);
+	let mut readme = Readme::new(template, input, options);
+	readme.write_markdown().unwrap();
+	// reference names are __link0, __link1, ... so we sort them by length first
+	let mut hrefs = readme.links.clone().into_iter().collect::<Vec<_>>();
+	hrefs.sort_by(|(lhs, _), (rhs, _)| {
+		lhs.len().cmp(&rhs.len()).then_with(|| lhs.cmp(rhs))
+	});
+	readme.write_links(false, diagnostics);
+
+	let dump = InputDump {
+		krate: &input.crate_name,
+		krate_version: &input.crate_version,
+		lib_name: &input.lib_name,
+		target: input.target_type,
+		dependencies: input
+			.dependencies
+			.iter()
+			.map(|(name, dep)| {
+				(name.as_str(), DependencyDump {
+					krate: &dep.crate_name,
+					lib_name: &dep.lib_name,
+					req: &dep.req,
+					version: &dep.version
+				})
+			})
+			.collect(),
+		links: hrefs
+			.into_iter()
+			.map(|(name, href)| LinkDump {
+				href,
+				url: readme.links[&name].clone()
+			})
+			.collect()
+	};
+	serde_json::to_writer_pretty(&mut *out_file, &dump)?;
+	writeln!(out_file)?;
+	Ok(())
+}
+
+/// Write a JSON object that maps the text of every heading in the readme generated from
+/// the rustdoc to the anchor that GitHub creates for it to `out_file`. Headings from
+/// the template are not included.
+pub fn emit_anchors(
+	input: &InputFile,
+	template: &str,
+	options: &EmitOptions,
+	out_file: &mut dyn io::Write
+) -> anyhow::Result<()> {
+	let template = options.template(template);
+	let mut readme = Readme::new(template, input, options);
+	readme.write_markdown().unwrap();
+
+	let mut anchors = BTreeMap::new();
+	for (heading, anchor) in heading_anchors(&readme.readme) {
+		anchors.entry(heading.trim().to_owned()).or_insert(anchor);
+	}
+
+	serde_json::to_writer_pretty(&mut *out_file, &anchors)?;
+	writeln!(out_file)?;
+	Ok(())
+}
+
 #[derive(Serialize)]
 struct TemplateContext<'a> {
 	#[serde(rename = "crate")]
@@ -383,52 +1312,495 @@ struct TemplateContext<'a> {
 	#[serde(rename = "crate_version")]
 	krate_version: &'a str,
 	target: TargetType,
+	title: &'a str,
+	docs_rs_url: String,
+	crates_io_url: String,
 
 	repository: Option<&'a str>,
 	repository_host: Option<String>,
+	repository_kind: Option<RepositoryKind>,
+	repository_path: Option<String>,
+	/// The path of the repository without its last segment, e.g. the user or group.
+	repository_owner: Option<String>,
+	/// The last segment of the path of the repository.
+	repository_name: Option<String>,
+	/// The repository as a url that can be opened in a browser.
+	repository_url: Option<String>,
 
 	license: Option<&'a str>,
+	authors: &'a [String],
 	rust_version: Option<&'a Version>,
+	min_version: Option<&'a str>,
+	msrv_note: bool,
+	badge_style: BadgeStyle,
+
+	keywords: &'a [String],
+	categories: &'a [String],
+	dependencies: Vec<DependencyView<'a>>,
 
 	readme: String,
-	links: String
+	links: String,
+	/// The rustdoc before it was processed, i.e. with the headings not increased and the
+	/// links not resolved.
+	raw_rustdoc: &'a str
 }
 
+/// A direct dependency of the crate as exposed to the template.
+#[derive(Serialize)]
+struct DependencyView<'a> {
+	name: &'a str,
+	req: String,
+	version: String,
+	kind: DependencyKind
+}
+
+impl<'a> DependencyView<'a> {
+	/// Return the dependencies of the crate, sorted by name. The crate itself is not
+	/// included.
+	fn list(input: &'a InputFile) -> Vec<Self> {
+		input
+			.dependencies
+			.values()
+			.filter(|dep| dep.crate_name != input.crate_name)
+			.map(|dep| Self {
+				name: &dep.crate_name,
+				req: dep.req.to_string(),
+				version: dep.version.to_string(),
+				kind: dep.kind
+			})
+			.sorted_by(|lhs, rhs| lhs.name.cmp(rhs.name))
+			.collect()
+	}
+}
+
+/// The maximum number of keywords that crates.io allows.
+const CRATES_IO_MAX_KEYWORDS: usize = 5;
+
+/// Template filter that returns the crates.io url of a category slug.
+fn crates_io_category(slug: &str) -> Result<String, minijinja::Error> {
+	// slugs look like `development-tools::cargo-plugins`
+	let valid = slug.split("::").all(|part| {
+		!part.is_empty()
+			&& part
+				.chars()
+				.all(|ch| ch.is_ascii_lowercase() || ch.is_ascii_digit() || ch == '-')
+	});
+	if !valid {
+		return Err(minijinja::Error::new(
+			minijinja::ErrorKind::InvalidOperation,
+			format!("`{slug}` is not a valid crates.io category slug")
+		));
+	}
+	Ok(format!("https://crates.io/categories/{slug}"))
+}
+
+/// Template filter that truncates the keywords to the number that crates.io allows.
+fn crates_io_keywords(mut keywords: Vec<String>) -> Vec<String> {
+	keywords.truncate(CRATES_IO_MAX_KEYWORDS);
+	keywords
+}
+
+/// Emit the readme to `out_file`. If `dep_info_file` is present, the dependency info is
+/// written to it instead of being included in the readme. Warnings about the generated
+/// readme, like links to crates that are not a dependency, are added to `diagnostics`.
 pub fn emit(
 	input: InputFile,
 	template: &str,
-	out_file: &mut dyn io::Write
+	options: &EmitOptions,
+	out_file: &mut dyn io::Write,
+	dep_info_file: Option<&mut dyn io::Write>,
+	diagnostics: &mut Diagnostic
 ) -> anyhow::Result<()> {
-	let mut readme = Readme::new(template, &input);
+	let template = options.template(template);
+	let mut readme = Readme::new(template, &input, options);
 
 	// unwrap: This will never fail since we're only writing to a String.
 	// it is just inconvenient to write .unwrap() behind every single write!() invocation
 	readme.write_markdown().unwrap();
+	if is_empty_markdown(&readme.readme) && !options.links_only {
+		diagnostics.warn("The readme generated from the rustdoc is empty");
+	}
+
+	readme.write_links(dep_info_file.is_some(), diagnostics);
+	if options.links_only {
+		readme.readme = String::new();
+	} else if options.output_format != OutputFormat::Markdown {
+		let markdown = format!("{}\n{}", readme.readme, readme.readme_links);
+		readme.readme = options.output_format.render(&markdown);
+		readme.readme_links = String::new();
+	}
+	if let Some(lang) = &options.wrap_in_fence {
+		readme.readme = wrap_in_fence(&readme.readme, lang);
+	}
+
+	let mut body = Vec::new();
+	if options.links_only {
+		body.extend_from_slice(readme.readme_links.as_bytes());
+	} else if options.bare {
+		body.extend_from_slice(readme.readme.as_bytes());
+		if !readme.readme_links.is_empty() {
+			body.push(b'\n');
+			body.extend_from_slice(readme.readme_links.as_bytes());
+		}
+	} else {
+		render_template(
+			&input,
+			template,
+			readme.readme,
+			readme.readme_links,
+			options,
+			&mut body
+		)?;
+	}
+	if options.emit_content_hash {
+		if !body.is_empty() && !body.ends_with(b"\n") {
+			body.push(b'\n');
+		}
+		let comment = content_hash_comment(&body);
+		body.extend_from_slice(comment.as_bytes());
+	}
+	out_file.write_all(&body)?;
+
+	if let (Some(dep_info_file), Some(dep_info)) = (dep_info_file, readme.dep_info) {
+		writeln!(dep_info_file, "{dep_info}")?;
+	}
 
-	readme.write_links();
+	Ok(())
+}
+
+/// The prefix of the comment that contains the hash of the readme.
+pub const CONTENT_HASH_PREFIX: &str = "<!-- doc2readme-hash: ";
+
+/// Return the comment with the blake3 hash of the body, which is everything in the readme
+/// before this comment.
+fn content_hash_comment(body: &[u8]) -> String {
+	format!("{CONTENT_HASH_PREFIX}{} -->\n", blake3::hash(body).to_hex())
+}
 
+/// Wrap the markdown in a fenced code block with the language `lang`. The fence is
+/// longer than any sequence of backticks in the markdown, so that it cannot be closed
+/// early.
+fn wrap_in_fence(markdown: &str, lang: &str) -> String {
+	let longest = markdown
+		.split(|ch| ch != '`')
+		.map(str::len)
+		.max()
+		.unwrap_or_default();
+	let fence = "`".repeat(longest.max(2) + 1);
+	let newline = if markdown.ends_with('\n') { "" } else { "\n" };
+	format!("{fence}{lang}\n{markdown}{newline}{fence}\n")
+}
+
+/// The software that hosts a repository, as far as it can be detected from the host.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum RepositoryKind {
+	Bitbucket,
+	GitHub,
+	GitLab,
+	SourceHut
+}
+
+impl RepositoryKind {
+	/// Detect the kind of the repository from its host. Self-hosted GitLab instances are
+	/// detected if their host contains `gitlab`.
+	fn from_host(host: &str) -> Option<Self> {
+		let host = host.to_lowercase();
+		Some(match host.as_str() {
+			"bitbucket.org" => Self::Bitbucket,
+			"github.com" => Self::GitHub,
+			"git.sr.ht" | "hg.sr.ht" => Self::SourceHut,
+			_ if host.contains("gitlab") => Self::GitLab,
+			_ => return None
+		})
+	}
+}
+
+/// Return the host and the path (usually `owner/repo`) of a repository url. Besides
+/// regular urls, this supports scp-like git urls like `git@github.com:owner/repo.git`.
+fn parse_repository(repo: &str) -> Option<(String, String)> {
+	let (host, path) = match Url::parse(repo) {
+		Ok(url) => (url.host_str()?.to_owned(), url.path().to_owned()),
+		Err(_) => {
+			// scp-like syntax is only recognized by git if there are no slashes before
+			// the first colon
+			let (host, path) = repo.split_once(':')?;
+			if host.is_empty() || host.contains('/') {
+				return None;
+			}
+			let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+			(host.to_owned(), path.to_owned())
+		}
+	};
+	let path = path.trim_matches('/');
+	let path = path.strip_suffix(".git").unwrap_or(path);
+	Some((host, path.to_owned()))
+}
+
+/// Render the template with the processed readme and links.
+fn render_template(
+	input: &InputFile,
+	template: &str,
+	readme: String,
+	links: String,
+	options: &EmitOptions,
+	out_file: &mut dyn io::Write
+) -> anyhow::Result<()> {
 	let repository = input.repository.as_deref();
+	let title = input.metadata.title.as_deref().unwrap_or(&input.crate_name);
+	let title = match &options.title_format {
+		Some(format) => format.format(&input.crate_name, &input.crate_version, title),
+		None => title.to_owned()
+	};
+	let (repository_host, repository_path) = repository
+		.and_then(parse_repository)
+		.map_or((None, None), |(host, path)| (Some(host), Some(path)));
+	let repository_kind = repository_host
+		.as_deref()
+		.and_then(RepositoryKind::from_host);
+	let (repository_owner, repository_name) = repository_path
+		.as_deref()
+		.and_then(|path| path.rsplit_once('/'))
+		.map_or((None, None), |(owner, name)| {
+			(Some(owner.to_owned()), Some(name.to_owned()))
+		});
+	let repository_url =
+		repository.map(|repo| match (&repository_host, &repository_path) {
+			(Some(host), Some(path)) if !repo.starts_with("http") => {
+				format!("https://{host}/{path}")
+			},
+			_ => repo.to_owned()
+		});
 	let ctx = TemplateContext {
 		krate: &input.crate_name,
 		krate_version: &format!("{}", input.crate_version),
 		target: input.target_type,
+		title: &title,
+		docs_rs_url: format!(
+			"{}/{}/{}/{}/",
+			options
+				.docs_rs_base_url
+				.as_deref()
+				.unwrap_or("https://docs.rs")
+				.trim_end_matches('/'),
+			input.crate_name,
+			input.crate_version,
+			input.lib_name
+		),
+		crates_io_url: format!(
+			"{}/crates/{}/{}",
+			options
+				.crates_io_base_url
+				.as_deref()
+				.unwrap_or("https://crates.io")
+				.trim_end_matches('/'),
+			input.crate_name,
+			input.crate_version
+		),
 		repository,
-		repository_host: repository.and_then(|repo| {
-			let url = Url::parse(repo).ok();
-			url.as_ref()
-				.and_then(|url| url.host_str())
-				.map(String::from)
-		}),
+		repository_host,
+		repository_kind,
+		repository_path,
+		repository_owner,
+		repository_name,
+		repository_url,
 		license: input.license.as_deref(),
+		authors: &input.authors,
 		rust_version: input.rust_version.as_ref(),
-		readme: readme.readme,
-		links: readme.readme_links
+		min_version: input.metadata.min_version.as_deref(),
+		msrv_note: input.metadata.msrv_note,
+		badge_style: input.metadata.badge_style,
+		keywords: &input.keywords,
+		categories: &input.categories,
+		dependencies: DependencyView::list(input),
+		readme,
+		links,
+		raw_rustdoc: &input.rustdoc
 	};
 
+	template_env(template)?
+		.get_template("template")?
+		.render_to_write(ctx, out_file)?;
+	Ok(())
+}
+
+fn template_env(template: &str) -> Result<minijinja::Environment<'_>, minijinja::Error> {
 	let mut env = minijinja::Environment::new();
+	env.add_filter("crates_io_category", crates_io_category);
+	env.add_filter("crates_io_keywords", crates_io_keywords);
 	env.add_template("template", template)?;
-	env.get_template("template")?
-		.render_to_write(ctx, out_file)?;
+	Ok(env)
+}
 
-	Ok(())
+/// Render the template with placeholder values instead of the data of a crate, and
+/// report any error to `diagnostics`. This allows checking a template without a crate.
+pub fn validate_template(template: &str, diagnostics: &mut Diagnostic) {
+	let rust_version = Version::new(1, 61, 0);
+	let ctx = TemplateContext {
+		krate: "my-crate",
+		krate_version: "0.1.0",
+		target: TargetType::Lib,
+		title: "my-crate",
+		docs_rs_url: "https://docs.rs/my-crate/0.1.0/my_crate/".into(),
+		crates_io_url: "https://crates.io/crates/my-crate/0.1.0".into(),
+		repository: Some("https://github.com/user/my-crate"),
+		repository_host: Some("github.com".into()),
+		repository_kind: Some(RepositoryKind::GitHub),
+		repository_path: Some("user/my-crate".into()),
+		repository_owner: Some("user".into()),
+		repository_name: Some("my-crate".into()),
+		repository_url: Some("https://github.com/user/my-crate".into()),
+		license: Some("MIT OR Apache-2.0"),
+		authors: &["Jane Doe <jane@example.org>".into()],
+		rust_version: Some(&rust_version),
+		min_version: None,
+		msrv_note: true,
+		badge_style: BadgeStyle::Row,
+		keywords: &["example".into()],
+		categories: &["development-tools::cargo-plugins".into()],
+		dependencies: vec![DependencyView {
+			name: "serde",
+			req: "^1.0".into(),
+			version: "1.0.200".into(),
+			kind: DependencyKind::Normal
+		}],
+		readme: "This is the readme of [`my-crate`][__link0].\n".into(),
+		links: " [__link0]: https://crates.io/crates/my-crate\n".into(),
+		raw_rustdoc: "This is the readme of [`my-crate`](crate).\n"
+	};
+
+	let result = template_env(template).and_then(|env| {
+		env.get_template("template")?
+			.render_to_write(ctx, io::sink())
+			.map(|_| ())
+	});
+	if let Err(err) = result {
+		diagnostics.template_error(&err);
+	}
+}
+
+/// The comment that marks the start of the generated section in a file that contains
+/// other content besides the readme.
+pub const MARKER_START: &str = "<!-- cargo-doc2readme start -->";
+
+/// The comment that marks the end of the generated section.
+pub const MARKER_END: &str = "<!-- cargo-doc2readme end -->";
+
+/// Find the region between the start and end marker in `buf`. The line break after the
+/// start marker is not part of the region.
+pub fn marker_region(buf: &[u8]) -> Option<Range<usize>> {
+	let mut start = memmem::find(buf, MARKER_START.as_bytes())? + MARKER_START.len();
+	if buf[start ..].starts_with(b"\r\n") {
+		start += 2;
+	} else if buf[start ..].starts_with(b"\n") {
+		start += 1;
+	}
+	let end = start + memmem::find(&buf[start ..], MARKER_END.as_bytes())?;
+	Some(start .. end)
+}
+
+/// Replace the region between the markers in `content` with `readme`.
+pub fn replace_marker_region(content: &str, readme: &str) -> anyhow::Result<String> {
+	let Some(region) = marker_region(content.as_bytes()) else {
+		bail!("Unable to find the `{MARKER_START}` and `{MARKER_END}` markers");
+	};
+	let mut out = String::with_capacity(content.len() + readme.len());
+	out += &content[.. region.start];
+	out += readme;
+	out += &content[region.end ..];
+	Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse_repository, validate_template, RepositoryKind};
+	use crate::diagnostic::Diagnostic;
+
+	fn parsed(host: &str, path: &str) -> Option<(String, String)> {
+		Some((host.into(), path.into()))
+	}
+
+	#[test]
+	fn parse_https_repository() {
+		assert_eq!(
+			parse_repository("https://github.com/owner/repo"),
+			parsed("github.com", "owner/repo")
+		);
+	}
+
+	#[test]
+	fn parse_scp_repository() {
+		assert_eq!(
+			parse_repository("git@github.com:owner/repo.git"),
+			parsed("github.com", "owner/repo")
+		);
+	}
+
+	#[test]
+	fn parse_ssh_repository() {
+		assert_eq!(
+			parse_repository("ssh://git@gitlab.com/owner/repo.git"),
+			parsed("gitlab.com", "owner/repo")
+		);
+	}
+
+	#[test]
+	fn parse_git_repository() {
+		assert_eq!(
+			parse_repository("git://codeberg.org/owner/repo.git"),
+			parsed("codeberg.org", "owner/repo")
+		);
+	}
+
+	#[test]
+	fn parse_invalid_repository() {
+		assert_eq!(parse_repository("owner/repo"), None);
+	}
+
+	#[test]
+	fn content_hash_comment() {
+		let hash = super::content_hash_comment(b"# readme\n\nSome text.\n");
+		assert!(hash.starts_with(super::CONTENT_HASH_PREFIX));
+		assert!(hash.ends_with(" -->\n"));
+		assert_eq!(
+			hash,
+			super::content_hash_comment(b"# readme\n\nSome text.\n")
+		);
+		assert_ne!(
+			hash,
+			super::content_hash_comment(b"# readme\n\nOther text.\n")
+		);
+	}
+
+	#[test]
+	fn repository_kind() {
+		let kind = RepositoryKind::from_host;
+		assert_eq!(kind("github.com"), Some(RepositoryKind::GitHub));
+		assert_eq!(kind("gitlab.com"), Some(RepositoryKind::GitLab));
+		assert_eq!(kind("gitlab.example.org"), Some(RepositoryKind::GitLab));
+		assert_eq!(kind("bitbucket.org"), Some(RepositoryKind::Bitbucket));
+		assert_eq!(kind("git.sr.ht"), Some(RepositoryKind::SourceHut));
+		assert_eq!(kind("codeberg.org"), None);
+	}
+
+	fn validate(template: &str) -> Diagnostic {
+		let mut diagnostics = Diagnostic::new("README.j2".into(), template.into());
+		validate_template(template, &mut diagnostics);
+		diagnostics
+	}
+
+	#[test]
+	fn validate_default_template() {
+		assert!(!validate(include_str!("README.j2")).is_fail());
+	}
+
+	#[test]
+	fn validate_template_syntax_error() {
+		assert!(validate("# {{ crate }}\n\n{{ readme }\n").is_fail());
+	}
+
+	#[test]
+	fn validate_template_render_error() {
+		assert!(validate("{{ \"Not A Slug\" | crates_io_category }}\n").is_fail());
+	}
 }
@@ -117,6 +117,7 @@ where
 				}
 			}
 		}
+		#[allow(clippy::needless_as_bytes)]
 		let common_indent_len = common_indent
 			.map(|common| common.as_bytes().len())
 			.unwrap_or(0);
@@ -0,0 +1,312 @@
+//! Render the generated markdown in output formats other than markdown.
+
+use pulldown_cmark::{
+	CodeBlockKind, Event, HeadingLevel, Options, Parser, Tag, TagEnd, TextMergeStream
+};
+use std::borrow::Cow;
+
+/// An output format that the generated markdown can be converted into.
+pub(crate) trait Renderer {
+	/// Process the next event of the markdown.
+	fn event(&mut self, event: Event<'_>);
+
+	/// Return the rendered output.
+	fn finish(self) -> String;
+}
+
+/// Convert the markdown using the renderer. Reference-style links are resolved by the
+/// markdown parser, so the link definitions need to be part of the markdown.
+pub(crate) fn render<R: Renderer>(mut renderer: R, markdown: &str) -> String {
+	// consecutive text events are merged so that text can be escaped as a whole
+	for event in TextMergeStream::new(Parser::new_ext(markdown, Options::all())) {
+		renderer.event(event);
+	}
+	let mut out = renderer.finish();
+	out.truncate(out.trim_end().len());
+	out.push('\n');
+	out
+}
+
+fn heading_level(level: HeadingLevel) -> usize {
+	match level {
+		HeadingLevel::H1 => 1,
+		HeadingLevel::H2 => 2,
+		HeadingLevel::H3 => 3,
+		HeadingLevel::H4 => 4,
+		HeadingLevel::H5 => 5,
+		HeadingLevel::H6 => 6
+	}
+}
+
+/// Make sure that the output ends with a newline, unless it is empty.
+fn ensure_newline(out: &mut String) {
+	if !out.is_empty() && !out.ends_with('\n') {
+		out.push('\n');
+	}
+}
+
+/// Make sure that the output ends with exactly one blank line, unless it is empty.
+fn ensure_blank_line(out: &mut String) {
+	out.truncate(out.trim_end_matches('\n').len());
+	if !out.is_empty() {
+		out.push_str("\n\n");
+	}
+}
+
+/// Characters that have a special meaning in AsciiDoc text.
+const ASCIIDOC_SPECIAL_CHARS: [char; 10] =
+	['*', '_', '`', '#', '[', ']', '+', '^', '~', '{'];
+
+/// Escape text for AsciiDoc. Text that contains special characters is wrapped in a
+/// passthrough that only replaces the characters that are special in HTML.
+fn escape_asciidoc(text: &str) -> Cow<'_, str> {
+	if text.contains(ASCIIDOC_SPECIAL_CHARS) {
+		format!("pass:c[{}]", text.replace(']', "\\]")).into()
+	} else {
+		text.into()
+	}
+}
+
+/// Renders AsciiDoc, e.g. `== Heading`, `link:url[text]` and `----` code blocks.
+#[derive(Default)]
+pub(crate) struct AsciiDoc {
+	out: String,
+	/// Whether we are in a code block, whose text is written as-is.
+	in_code_block: bool,
+	/// The start of the ordered (`Some`) and unordered (`None`) lists we are in.
+	lists: Vec<Option<u64>>,
+	/// The number of paragraphs in the current list item.
+	item_paragraphs: usize,
+	/// The start of the text and the destination of links and images we are in.
+	links: Vec<(usize, String, bool)>
+}
+
+impl Renderer for AsciiDoc {
+	fn event(&mut self, event: Event<'_>) {
+		let out = &mut self.out;
+		match event {
+			Event::Start(Tag::Paragraph) if !self.lists.is_empty() => {
+				if self.item_paragraphs > 0 {
+					ensure_newline(out);
+					out.push_str("+\n");
+				}
+				self.item_paragraphs += 1;
+			},
+			Event::End(TagEnd::Paragraph) => {
+				if self.lists.is_empty() {
+					ensure_blank_line(out);
+				} else {
+					ensure_newline(out);
+				}
+			},
+			Event::Start(Tag::Heading { level, .. }) => {
+				ensure_blank_line(out);
+				out.push_str(&"=".repeat(heading_level(level)));
+				out.push(' ');
+			},
+			Event::End(TagEnd::Heading(_)) => ensure_blank_line(out),
+			Event::Start(Tag::BlockQuote(_)) => {
+				ensure_blank_line(out);
+				out.push_str("____\n");
+			},
+			Event::End(TagEnd::BlockQuote(_)) => {
+				ensure_newline(out);
+				out.truncate(out.trim_end_matches('\n').len() + 1);
+				out.push_str("____\n\n");
+			},
+			Event::Start(Tag::CodeBlock(kind)) => {
+				if self.lists.is_empty() {
+					ensure_blank_line(out);
+				} else {
+					ensure_newline(out);
+					out.push_str("+\n");
+				}
+				if let CodeBlockKind::Fenced(info) = kind {
+					if let Some(lang) = info
+						.split([',', ' '])
+						.next()
+						.filter(|lang| !lang.is_empty())
+					{
+						out.push_str(&format!("[source,{lang}]\n"));
+					}
+				}
+				out.push_str("----\n");
+				self.in_code_block = true;
+			},
+			Event::End(TagEnd::CodeBlock) => {
+				self.in_code_block = false;
+				ensure_newline(out);
+				out.push_str("----\n");
+				if self.lists.is_empty() {
+					out.push('\n');
+				}
+			},
+			Event::Start(Tag::HtmlBlock) => {
+				ensure_blank_line(out);
+				out.push_str("++++\n");
+			},
+			Event::End(TagEnd::HtmlBlock) => {
+				ensure_newline(out);
+				out.push_str("++++\n\n");
+			},
+			Event::Start(Tag::List(start)) => {
+				if self.lists.is_empty() {
+					ensure_blank_line(out);
+				} else {
+					ensure_newline(out);
+				}
+				self.lists.push(start);
+			},
+			Event::End(TagEnd::List(_)) => {
+				self.lists.pop();
+				if self.lists.is_empty() {
+					ensure_blank_line(out);
+				}
+			},
+			Event::Start(Tag::Item) => {
+				ensure_newline(out);
+				let marker = match self.lists.last() {
+					Some(Some(_)) => ".",
+					_ => "*"
+				};
+				out.push_str(&marker.repeat(self.lists.len()));
+				out.push(' ');
+				self.item_paragraphs = 0;
+			},
+			Event::End(TagEnd::Item) => ensure_newline(out),
+			Event::Start(Tag::FootnoteDefinition(name)) => {
+				ensure_blank_line(out);
+				out.push_str(&format!("[{name}] "));
+			},
+			Event::End(TagEnd::FootnoteDefinition) => ensure_blank_line(out),
+			Event::Start(Tag::Table(_)) => {
+				ensure_blank_line(out);
+				out.push_str("|===\n");
+			},
+			Event::End(TagEnd::Table) => {
+				ensure_newline(out);
+				out.push_str("|===\n\n");
+			},
+			Event::End(TagEnd::TableHead) => out.push_str("\n\n"),
+			Event::End(TagEnd::TableRow) => out.push('\n'),
+			Event::Start(Tag::TableCell) => {
+				if !out.ends_with('\n') {
+					out.push(' ');
+				}
+				out.push('|');
+			},
+			Event::Start(Tag::Emphasis) | Event::End(TagEnd::Emphasis) => out.push('_'),
+			Event::Start(Tag::Strong) | Event::End(TagEnd::Strong) => out.push('*'),
+			Event::Start(Tag::Strikethrough) => out.push_str("[.line-through]#"),
+			Event::End(TagEnd::Strikethrough) => out.push('#'),
+			Event::Start(Tag::Link { dest_url, .. }) => {
+				self.links.push((out.len(), dest_url.into_string(), false));
+			},
+			Event::Start(Tag::Image { dest_url, .. }) => {
+				self.links.push((out.len(), dest_url.into_string(), true));
+			},
+			Event::End(TagEnd::Link | TagEnd::Image) => {
+				if let Some((start, dest_url, image)) = self.links.pop() {
+					let text = out.split_off(start);
+					let macro_name = if image { "image" } else { "link" };
+					out.push_str(&format!("{macro_name}:{dest_url}[{text}]"));
+				}
+			},
+			Event::Text(text) if !self.in_code_block => {
+				out.push_str(&escape_asciidoc(&text));
+			},
+			Event::Text(text) | Event::Html(text) | Event::InlineMath(text) => {
+				out.push_str(&text);
+			},
+			Event::DisplayMath(text) => {
+				out.push_str(&format!("stem:[{text}]"));
+			},
+			// the `+` passthrough cannot contain a `+` itself
+			Event::Code(code) if code.contains('+') => {
+				out.push_str(&format!("`{}`", escape_asciidoc(&code)));
+			},
+			Event::Code(code) => out.push_str(&format!("`+{code}+`")),
+			Event::InlineHtml(html) => out.push_str(&format!("+++{html}+++")),
+			Event::FootnoteReference(name) => out.push_str(&format!("[{name}]")),
+			Event::SoftBreak => out.push('\n'),
+			Event::HardBreak => out.push_str(" +\n"),
+			Event::Rule => {
+				ensure_blank_line(out);
+				out.push_str("'''\n\n");
+			},
+			Event::TaskListMarker(checked) => {
+				out.push_str(if checked { "[x] " } else { "[ ] " });
+			},
+			_ => {}
+		}
+	}
+
+	fn finish(self) -> String {
+		self.out
+	}
+}
+
+/// Renders plain text without any formatting, e.g. for changelog snippets.
+#[derive(Default)]
+pub(crate) struct PlainText {
+	out: String,
+	/// The next number of the ordered (`Some`) and unordered (`None`) lists we are in.
+	lists: Vec<Option<u64>>
+}
+
+impl Renderer for PlainText {
+	fn event(&mut self, event: Event<'_>) {
+		let out = &mut self.out;
+		match event {
+			Event::End(
+				TagEnd::Paragraph
+				| TagEnd::Heading(_)
+				| TagEnd::BlockQuote(_)
+				| TagEnd::CodeBlock
+				| TagEnd::FootnoteDefinition
+				| TagEnd::Table
+			) if self.lists.is_empty() => ensure_blank_line(out),
+			Event::End(TagEnd::Paragraph | TagEnd::CodeBlock) => ensure_newline(out),
+			Event::Start(Tag::List(start)) => {
+				ensure_newline(out);
+				self.lists.push(start);
+			},
+			Event::End(TagEnd::List(_)) => {
+				self.lists.pop();
+				if self.lists.is_empty() {
+					ensure_blank_line(out);
+				}
+			},
+			Event::Start(Tag::Item) => {
+				ensure_newline(out);
+				out.push_str(&"  ".repeat(self.lists.len() - 1));
+				match self.lists.last_mut() {
+					Some(Some(number)) => {
+						out.push_str(&format!("{number}. "));
+						*number += 1;
+					},
+					_ => out.push_str("- ")
+				}
+			},
+			Event::End(TagEnd::Item) => ensure_newline(out),
+			Event::Start(Tag::TableCell) if !out.is_empty() && !out.ends_with('\n') => {
+				out.push('\t');
+			},
+			Event::End(TagEnd::TableHead | TagEnd::TableRow) => out.push('\n'),
+			Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+				out.push_str(&text);
+			},
+			Event::DisplayMath(text) => out.push_str(&text),
+			Event::FootnoteReference(name) => out.push_str(&format!("[{name}]")),
+			Event::SoftBreak | Event::HardBreak => out.push('\n'),
+			Event::TaskListMarker(checked) => {
+				out.push_str(if checked { "[x] " } else { "[ ] " });
+			},
+			_ => {}
+		}
+	}
+
+	fn finish(self) -> String {
+		self.out
+	}
+}
@@ -1,7 +1,51 @@
-use crate::{depinfo::DependencyInfo, diagnostic::Diagnostic, input::InputFile, output};
+use crate::{
+	depinfo::DependencyInfo,
+	diagnostic::Diagnostic,
+	input::InputFile,
+	output::{self, EmitOptions}
+};
+use anyhow::bail;
+use clap::ValueEnum;
 use log::debug;
-use memchr::{memchr2, memmem};
-use std::{io, process::ExitCode};
+use memchr::memmem;
+use semver::{Comparator, Op, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, io, process::ExitCode};
+
+/// How the dependency versions stored in the readme are compared to the current ones.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckDepMode {
+	/// Accept any version that satisfies the current version requirement.
+	#[default]
+	Compatible,
+	/// Require the exact version that the dependency currently resolves to.
+	Exact
+}
+
+/// How the result of the check is printed.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, ValueEnum)]
+pub enum MessageFormat {
+	/// Print a human readable message to stderr.
+	#[default]
+	Human,
+	/// Print a JSON object with the status to stdout.
+	Json
+}
+
+/// Options that alter how the readme is checked.
+#[derive(Clone, Debug, Default)]
+pub struct CheckOptions {
+	/// How the dependency versions are compared.
+	pub dep_mode: CheckDepMode,
+
+	/// Only check the region between the start and end markers.
+	pub markers: bool,
+
+	/// Compare the content of the readme instead of the dependency info, ignoring the
+	/// line with the dependency info.
+	pub ignore_dep_info: bool
+}
 
 pub enum Check {
 	/// Everything is up to date.
@@ -10,8 +54,11 @@ pub enum Check {
 	/// The dependency info is malformed.
 	InvalidDepInfo(anyhow::Error),
 
-	/// The input (template or rustdoc) have changed.
-	InputChanged,
+	/// The template has changed.
+	TemplateChanged,
+
+	/// The rustdoc has changed.
+	RustdocChanged,
 
 	/// One or more dependencies use an incompatible version.
 	IncompatibleVersion(String),
@@ -19,6 +66,10 @@ pub enum Check {
 	/// The readme used an outdated "markdown version".
 	OutdatedMarkdown,
 
+	/// The readme used a "markdown version" that is newer than the one of this tool.
+	/// Everything else is up to date.
+	NewerMarkdown,
+
 	/// Input and output are different (no dep info was included).
 	OutputChanged
 }
@@ -41,7 +92,8 @@ impl Check {
 			Check::InvalidDepInfo(e) => {
 				diag.warn(format_args!("Readme has invalid dependency info: {e}"));
 			},
-			Check::InputChanged => diag.error("Input has changed"),
+			Check::TemplateChanged => diag.error("Template has changed"),
+			Check::RustdocChanged => diag.error("Rustdoc has changed"),
 			Check::IncompatibleVersion(name) => {
 				diag.error(format_args!(
 					"Readme links to incompatible version of dependency `{name}`"
@@ -52,6 +104,12 @@ impl Check {
 					"The readme was created with an outdated version of this tool"
 				);
 			},
+			Check::NewerMarkdown => {
+				diag.warn(
+					"The readme was created with a newer version of this tool, \
+					 consider upgrading cargo-doc2readme"
+				);
+			},
 			Check::OutputChanged => {
 				diag.error("Readme has changed");
 			}
@@ -59,8 +117,67 @@ impl Check {
 		diag.print_to(out)
 	}
 
+	/// Like [`print`](Self::print), but prints nothing if the readme is up to date.
+	pub fn print_quiet<T: Into<String>>(&self, filename: T) -> io::Result<()> {
+		self.print_quiet_to(filename, io::stderr())
+	}
+
+	pub fn print_quiet_to<T, W>(&self, filename: T, out: W) -> Result<(), io::Error>
+	where
+		T: Into<String>,
+		W: io::Write
+	{
+		match self {
+			Check::UpToDate => Ok(()),
+			_ => self.print_to(filename, out)
+		}
+	}
+
+	/// Print the result as a JSON object with the `status` of the check.
+	pub fn print_json(&self) -> io::Result<()> {
+		self.print_json_to(io::stdout())
+	}
+
+	pub fn print_json_to<W: io::Write>(&self, mut out: W) -> io::Result<()> {
+		serde_json::to_writer(&mut out, &CheckView::from(self))?;
+		writeln!(out)
+	}
+
 	pub fn is_ok(&self) -> bool {
-		matches!(self, Self::UpToDate)
+		matches!(self, Self::UpToDate | Self::NewerMarkdown)
+	}
+}
+
+/// The serializable view of a [`Check`].
+#[derive(Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+enum CheckView<'a> {
+	UpToDate,
+	InvalidDepInfo { error: String },
+	TemplateChanged,
+	RustdocChanged,
+	IncompatibleVersion { dependency: &'a str },
+	OutdatedMarkdown,
+	NewerMarkdown,
+	OutputChanged
+}
+
+impl<'a> From<&'a Check> for CheckView<'a> {
+	fn from(check: &'a Check) -> Self {
+		match check {
+			Check::UpToDate => Self::UpToDate,
+			Check::InvalidDepInfo(e) => Self::InvalidDepInfo {
+				error: e.to_string()
+			},
+			Check::TemplateChanged => Self::TemplateChanged,
+			Check::RustdocChanged => Self::RustdocChanged,
+			Check::IncompatibleVersion(name) => {
+				Self::IncompatibleVersion { dependency: name }
+			},
+			Check::OutdatedMarkdown => Self::OutdatedMarkdown,
+			Check::NewerMarkdown => Self::NewerMarkdown,
+			Check::OutputChanged => Self::OutputChanged
+		}
 	}
 }
 
@@ -76,53 +193,212 @@ impl From<Check> for ExitCode {
 pub fn check_up2date(
 	input: InputFile,
 	template: &str,
-	check_file: &mut dyn io::Read
+	options: &EmitOptions,
+	check_options: &CheckOptions,
+	check_file: &mut dyn io::Read,
+	dep_info_file: Option<&mut dyn io::Read>
 ) -> anyhow::Result<Check> {
+	let template = options.template(template);
 	let mut check_buf = Vec::new();
 	check_file.read_to_end(&mut check_buf)?;
-
-	let search_key = b" [__cargo_doc2readme_dependencies_info]: ";
-	if let Some(search_idx) = memmem::find(&check_buf, search_key) {
-		let sub = &check_buf[search_idx + search_key.len() ..];
-		let end_idx = memchr2(b' ', b'\n', sub).unwrap_or(sub.len());
-		let depinfo_str = String::from_utf8(sub[.. end_idx].to_vec()).unwrap();
-		let depinfo = match DependencyInfo::decode(depinfo_str) {
-			Ok(depinfo) => depinfo,
-			Err(e) => {
-				return Ok(Check::InvalidDepInfo(e));
-			}
+	if check_options.markers {
+		let Some(region) = output::marker_region(&check_buf) else {
+			bail!(
+				"Unable to find the `{}` and `{}` markers",
+				output::MARKER_START,
+				output::MARKER_END
+			);
 		};
+		check_buf = check_buf[region].to_vec();
+	}
 
-		// ensure markdown version matches
-		if depinfo.check_outdated() {
-			return Ok(Check::OutdatedMarkdown);
-		}
-
-		// ensure the input is up to date
-		if !depinfo.check_input(template, &input.rustdoc) {
-			return Ok(Check::InputChanged);
-		}
+	if check_options.ignore_dep_info {
+		return check_output(input, template, options, &strip_dep_info(&check_buf), true);
+	}
 
-		// ensure that the dependencies that were used in the readme still meet the current required
-		// versions. dependencies that are missing in the readme don't matter.
-		for (lib_name, dep) in &input.dependencies {
-			debug!("Checking {} = \"{}\"", dep.crate_name, dep.req);
-			if !depinfo.check_dependency(&dep.crate_name, Some(&dep.req), lib_name, true)
-			{
-				return Ok(Check::IncompatibleVersion(dep.crate_name.clone()));
-			}
-		}
+	if let Some(search_idx) = memmem::find(&check_buf, DEP_INFO_KEY) {
+		let sub = &check_buf[search_idx + DEP_INFO_KEY.len() ..];
+		// the line might end with trailing whitespace or `\r\n`
+		let end_idx = sub
+			.iter()
+			.position(u8::is_ascii_whitespace)
+			.unwrap_or(sub.len());
+		let depinfo_str = String::from_utf8(sub[.. end_idx].to_vec()).unwrap();
+		return Ok(check_dep_info(
+			input,
+			&options.hashed_template(template),
+			check_options,
+			depinfo_str
+		));
+	}
 
-		// looks like everything is up to date
-		return Ok(Check::UpToDate);
+	// if the readme contains no dependency info, it might be stored in a sidecar file
+	if let Some(dep_info_file) = dep_info_file {
+		let mut depinfo_str = String::new();
+		dep_info_file.read_to_string(&mut depinfo_str)?;
+		let depinfo_str = depinfo_str.trim().to_owned();
+		return Ok(check_dep_info(
+			input,
+			&options.hashed_template(template),
+			check_options,
+			depinfo_str
+		));
 	}
 
 	// if no dependency info was available, do a bytewise comparison
+	check_output(input, template, options, &check_buf, false)
+}
+
+/// The start of the line that contains the dependency info.
+const DEP_INFO_KEY: &[u8] = b" [__cargo_doc2readme_dependencies_info]: ";
+
+/// Remove the line with the dependency info from the readme.
+fn strip_dep_info(buf: &[u8]) -> Vec<u8> {
+	buf.split_inclusive(|b| *b == b'\n')
+		.filter(|line| !line.starts_with(DEP_INFO_KEY))
+		.flatten()
+		.copied()
+		.collect()
+}
+
+/// Compare the readme bytewise with the output that would be generated now, optionally
+/// without the dependency info.
+fn check_output(
+	input: InputFile,
+	template: &str,
+	options: &EmitOptions,
+	check_buf: &[u8],
+	ignore_dep_info: bool
+) -> anyhow::Result<Check> {
+	// we only care about the output, any warnings were already reported when the readme
+	// was written
 	let mut output_buf = Vec::new();
-	output::emit(input, template, &mut output_buf)?;
+	let mut diagnostics = Diagnostic::new(String::new(), String::new());
+	output::emit(
+		input,
+		template,
+		options,
+		&mut output_buf,
+		None,
+		&mut diagnostics
+	)?;
+	if ignore_dep_info {
+		output_buf = strip_dep_info(&output_buf);
+	}
 	Ok(if output_buf == check_buf {
 		Check::UpToDate
 	} else {
 		Check::OutputChanged
 	})
 }
+
+fn check_dep_info(
+	input: InputFile,
+	template: &str,
+	options: &CheckOptions,
+	depinfo_str: String
+) -> Check {
+	let depinfo = match DependencyInfo::decode(depinfo_str) {
+		Ok(depinfo) => depinfo,
+		Err(e) => {
+			return Check::InvalidDepInfo(e);
+		}
+	};
+
+	// ensure markdown version is not outdated. if the readme was created by a newer
+	// version of this tool, we cannot reproduce it, but this is not the readme's fault
+	let newer_markdown = match depinfo.compare_markdown_version() {
+		Ordering::Less => return Check::OutdatedMarkdown,
+		Ordering::Equal => false,
+		Ordering::Greater => true
+	};
+
+	// ensure the input is up to date
+	if !depinfo.is_template_up2date(template) {
+		return Check::TemplateChanged;
+	}
+	if !depinfo.is_rustdoc_up2date(&input.rustdoc) {
+		return Check::RustdocChanged;
+	}
+
+	// ensure that the dependencies that were used in the readme still meet the current required
+	// versions. dependencies that are missing in the readme don't matter.
+	for dep in input.dependencies.values() {
+		let req = match options.dep_mode {
+			CheckDepMode::Compatible => dep.req.clone(),
+			CheckDepMode::Exact => VersionReq {
+				comparators: vec![Comparator {
+					op: Op::Exact,
+					major: dep.version.major,
+					minor: Some(dep.version.minor),
+					patch: Some(dep.version.patch),
+					pre: dep.version.pre.clone()
+				}]
+			}
+		};
+		debug!("Checking {} = \"{req}\"", dep.crate_name);
+		if !depinfo.check_dependency(&dep.crate_name, Some(&req), &dep.lib_name, true) {
+			return Check::IncompatibleVersion(dep.crate_name.clone());
+		}
+	}
+
+	// looks like everything is up to date
+	if newer_markdown {
+		Check::NewerMarkdown
+	} else {
+		Check::UpToDate
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Check;
+	use anyhow::anyhow;
+
+	fn json(check: Check) -> String {
+		let mut out = Vec::new();
+		check.print_json_to(&mut out).unwrap();
+		String::from_utf8(out).unwrap()
+	}
+
+	#[test]
+	fn json_status() {
+		let cases = [
+			(Check::UpToDate, r#"{"status":"up_to_date"}"#),
+			(
+				Check::InvalidDepInfo(anyhow!("Invalid base64")),
+				r#"{"status":"invalid_dep_info","error":"Invalid base64"}"#
+			),
+			(Check::TemplateChanged, r#"{"status":"template_changed"}"#),
+			(Check::RustdocChanged, r#"{"status":"rustdoc_changed"}"#),
+			(
+				Check::IncompatibleVersion("serde".into()),
+				r#"{"status":"incompatible_version","dependency":"serde"}"#
+			),
+			(Check::OutdatedMarkdown, r#"{"status":"outdated_markdown"}"#),
+			(Check::NewerMarkdown, r#"{"status":"newer_markdown"}"#),
+			(Check::OutputChanged, r#"{"status":"output_changed"}"#)
+		];
+		for (check, expected) in cases {
+			assert_eq!(json(check), format!("{expected}\n"));
+		}
+	}
+
+	#[test]
+	fn print_quiet_up_to_date() {
+		let mut out = Vec::new();
+		Check::UpToDate
+			.print_quiet_to("README.md", &mut out)
+			.unwrap();
+		assert!(out.is_empty());
+	}
+
+	#[test]
+	fn print_quiet_failure() {
+		let mut out = Vec::new();
+		Check::RustdocChanged
+			.print_quiet_to("README.md", &mut out)
+			.unwrap();
+		assert!(!out.is_empty());
+	}
+}
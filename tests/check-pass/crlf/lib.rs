@@ -0,0 +1,5 @@
+//! This readme was checked out with `\r\n` line endings, which must not break the
+//! dependency info of links like [`Item`].
+
+/// An item.
+pub struct Item;
@@ -0,0 +1,4 @@
+//! This links to [`Item`], so the readme contains dependency info.
+
+/// An item.
+pub struct Item;
@@ -0,0 +1 @@
+//! This crate's readme is embedded in a larger file. It links to [`serde::Serialize`].
@@ -0,0 +1 @@
+//! This crate links to [`serde::Serialize`].
@@ -0,0 +1,6 @@
+//! This crate implements [`Foo`] and [`Bar`], but the readme does not contain any
+//! dependency info.
+
+pub struct Foo;
+
+pub struct Bar;
@@ -0,0 +1,3 @@
+//! Changing the base url of the docs changes the link to [`Foo`].
+
+pub struct Foo;
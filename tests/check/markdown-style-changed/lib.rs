@@ -0,0 +1,6 @@
+//! Lists can use a different marker:
+//!
+//! - first item
+//! - second item linking to [`Foo`]
+
+pub struct Foo;
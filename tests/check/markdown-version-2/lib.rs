@@ -0,0 +1,7 @@
+//! Please send your letters to [Foo]:
+//!
+//! Jane Doe  
+//! 42 Example Street\
+//! Exampletown
+
+pub struct Foo;
@@ -0,0 +1,5 @@
+//! This crate implements [`Foo`] and [`Bar`].
+
+pub struct Foo;
+
+pub struct Bar;
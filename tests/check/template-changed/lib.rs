@@ -0,0 +1,3 @@
+//! This crate implements [`Foo`].
+
+pub struct Foo;
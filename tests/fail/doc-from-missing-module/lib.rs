@@ -0,0 +1 @@
+//! The crate has no module `missing`.
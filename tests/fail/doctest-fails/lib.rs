@@ -0,0 +1,10 @@
+//! This crate has an example that does not compile.
+//!
+//! ```rust
+//! let answer: u32 = doctest_fails::answer();
+//! ```
+
+/// The answer.
+pub fn answer() -> &'static str {
+	"42"
+}
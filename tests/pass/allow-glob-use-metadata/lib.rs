@@ -0,0 +1,8 @@
+//! Items imported through the glob use are not used for link generation, but the glob
+//! use is accepted without a warning. Other links like [`Item`] still work.
+
+#[allow(unused_imports)]
+use std::io::prelude::*;
+
+/// An item.
+pub struct Item;
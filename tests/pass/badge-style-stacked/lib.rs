@@ -0,0 +1 @@
+//! The badges are rendered in the `stacked` style.
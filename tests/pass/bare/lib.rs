@@ -0,0 +1,4 @@
+//! This readme has no title or badges, but links to [`Foo`].
+
+/// A struct.
+pub struct Foo;
@@ -0,0 +1,3 @@
+//! This is the helper binary, which is not the default.
+
+fn main() {}
@@ -0,0 +1,3 @@
+//! This is the default binary.
+
+fn main() {}
@@ -0,0 +1,5 @@
+//! Links to [`Item`], [`std::string::String`] and [`crate::Item`] can be resolved, but
+//! links to [`Missing`] and [`missing::Item`] cannot.
+
+/// An item.
+pub struct Item;
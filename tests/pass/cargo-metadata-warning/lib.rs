@@ -0,0 +1 @@
+//! The workspace of this crate does not set a resolver, which cargo warns about.
@@ -0,0 +1,7 @@
+//! This crate is only built as a [`cdylib`](https://doc.rust-lang.org/reference/linkage.html)
+//! and exports the [`add`] function.
+
+#[no_mangle]
+pub extern "C" fn add(a: i32, b: i32) -> i32 {
+	a + b
+}
@@ -0,0 +1,4 @@
+//! The [`Array`] type can be created from a literal like `[0, 1]`. Only the type name is
+//! a link, the literal must remain an inline code span.
+
+pub struct Array;
@@ -0,0 +1,20 @@
+//! Languages that contain a flag are kept as they are:
+//!
+//! ```ignore-me
+//! some code
+//! ```
+//!
+//! ```diff
+//! -let answer = 41;
+//! +let answer = 42;
+//! ```
+//!
+//! Flags are still removed, even if separated by spaces:
+//!
+//! ```text,no_run
+//! some text
+//! ```
+//!
+//! ```rust ignore
+//! let answer = 42;
+//! ```
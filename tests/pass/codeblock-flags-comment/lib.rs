@@ -0,0 +1,16 @@
+//! ```should_panic
+//! panic!("This example panics");
+//! ```
+//!
+//! ```rust,no_run,edition2021
+//! loop {}
+//! ```
+//!
+//! ```
+//! // This example has no flags.
+//! ```
+//!
+//! ```text,ignore
+//! The flags of other languages are kept as they are when preserved, but not added as a
+//! comment.
+//! ```
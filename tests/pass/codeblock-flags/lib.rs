@@ -0,0 +1,16 @@
+//! Rustdoc knows about the 2024 edition:
+//!
+//! ```edition2024
+//! let r#gen = 42;
+//! ```
+//!
+//! Custom flags can be configured in the package metadata:
+//!
+//! ```rust,custom_lint
+//! let x = 1;
+//! ```
+//!
+//! ```toml
+//! [package]
+//! name = "foo"
+//! ```
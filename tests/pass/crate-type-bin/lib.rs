@@ -0,0 +1 @@
+//! This is the documentation of the library, which is a `cdylib` and an `rlib`.
@@ -0,0 +1,8 @@
+//! # `dedup_title`
+//!
+//! This crate starts its documentation with its name, which is already part of the
+//! template.
+//!
+//! # Usage
+//!
+//! Other level 1 headings are kept.
@@ -0,0 +1 @@
+//! The default template detects the host of the repository.
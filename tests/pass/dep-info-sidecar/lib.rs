@@ -0,0 +1,3 @@
+//! This crate implements [`Foo`], but the readme does not contain any dependency info.
+
+pub struct Foo;
@@ -0,0 +1 @@
+//! This crate lists its dependencies.
@@ -0,0 +1,4 @@
+//! The documentation of the crate is not used.
+
+/// The documentation of the modules is not used.
+pub mod shapes;
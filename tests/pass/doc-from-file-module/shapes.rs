@@ -0,0 +1,7 @@
+//! Neither is the documentation of this module.
+
+/// The outer documentation of the nested module is used.
+pub mod round;
+
+/// A square.
+pub struct Square;
@@ -0,0 +1,5 @@
+//! The inner documentation of the nested module, which is in its own file, is used as
+//! well, but not the documentation of the items inside of it.
+
+/// A circle.
+pub struct Circle;
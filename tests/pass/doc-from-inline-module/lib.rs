@@ -0,0 +1,15 @@
+//! The documentation of the crate is not used.
+
+/// The outer documentation of the module is used.
+pub mod shapes {
+	//! Its inner documentation is used as well, but not the documentation of the items
+	//! inside of it.
+
+	/// A circle.
+	pub struct Circle;
+
+	/// Round shapes.
+	pub mod round {
+		//! Nor the documentation of nested modules.
+	}
+}
@@ -0,0 +1,12 @@
+#![doc(test(attr(deny(warnings))))]
+#![doc(html_root_url = "https://docs.rs/doc-list-attrs/0.0.0")]
+//! This crate configures its doc tests using `#![doc(test(...))]`, which must not
+//! be mistaken for documentation, but links to [`Foo`] still work.
+#![cfg_attr(docsrs, doc(test(no_crate_inject)))]
+#![doc(
+	html_logo_url = "https://example.org/logo.png",
+	html_favicon_url = "https://example.org/favicon.ico"
+)]
+
+#[doc(alias = "Bar")]
+pub struct Foo;
@@ -0,0 +1,4 @@
+//! Links to [`Item`] and [`String`] point to the configured mirrors.
+
+/// An item.
+pub struct Item;
@@ -0,0 +1,7 @@
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+//! This crate is documented on docs.rs.
+#![cfg_attr(docsrs, doc = "")]
+#![cfg_attr(docsrs, doc = "Features are annotated automatically.")]
+
+#[cfg(feature = "f")]
+pub fn foo() {}
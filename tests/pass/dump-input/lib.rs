@@ -0,0 +1,4 @@
+//! This crate depends on [`serde`] and links to [`serde::Serialize`] as well as its own
+//! [`Foo`] type.
+
+pub struct Foo;
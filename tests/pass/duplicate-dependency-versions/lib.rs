@@ -0,0 +1,2 @@
+//! Another member of the workspace depends on an older version of [`itoa`], but this
+//! crate links to the version it depends on itself, like [`itoa::Buffer`].
@@ -0,0 +1 @@
+//! With the 2024 prelude, [`Future`] and [`IntoFuture`] can be linked directly.
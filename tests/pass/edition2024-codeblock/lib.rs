@@ -0,0 +1,13 @@
+//! The `edition2024` flag is removed together with other flags:
+//!
+//! ```rust,edition2024
+//! let r#gen = 1;
+//! ```
+//!
+//! ```edition2024,no_run
+//! loop {}
+//! ```
+//!
+//! ```should_panic,edition2024
+//! panic!();
+//! ```
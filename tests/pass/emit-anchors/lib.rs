@@ -0,0 +1,13 @@
+//! # Getting Started
+//!
+//! Add this crate to your `Cargo.toml`.
+//!
+//! ## Using `Foo`
+//!
+//! Some text.
+//!
+//! # Examples
+//!
+//! ## Using `Foo`
+//!
+//! More text.
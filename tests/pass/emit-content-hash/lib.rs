@@ -0,0 +1,5 @@
+//! The readme ends with a comment that contains the hash of everything before it, e.g.
+//! this text and the link to [`Item`].
+
+/// An item.
+pub struct Item;
@@ -0,0 +1 @@
+//! Text can be *emphasized*, **strongly emphasized** or even ***both***.
@@ -0,0 +1,9 @@
+mod private {}
+
+/// This struct is the only thing this crate exports. Create one using [`Self::new`] or
+/// [`Default`].
+#[derive(Default)]
+pub struct Foo;
+
+/// This documentation is not used.
+pub struct Bar;
@@ -0,0 +1,18 @@
+//! Links to [`Extra`] and [`NoStd`] resolve as the `full` feature is active, but links
+//! to [`Std`] and [`Unknown`] do not. Links to [`Test`] resolve as it is not gated by a
+//! feature.
+
+#[cfg(feature = "std")]
+pub struct Std;
+
+#[cfg(not(feature = "std"))]
+pub struct NoStd;
+
+#[cfg(all(feature = "extra", not(feature = "std")))]
+pub struct Extra;
+
+#[cfg(any(feature = "unknown", feature = "std"))]
+pub struct Unknown;
+
+#[cfg(any(test, feature = "std"))]
+pub struct Test;
@@ -0,0 +1,11 @@
+//! Please send your letters to:
+//!
+//! Jane Doe  
+//! 42 Example Street\
+//! Exampletown
+//!
+//! > Quoted  
+//! > text
+//!
+//!  - List\
+//!    item
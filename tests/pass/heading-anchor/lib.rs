@@ -0,0 +1,14 @@
+//! This crate has a section about [the `Foo` type](#the-foo-type). Links that don't use
+//! the exact anchor, like [this one](#The-Foo-Type!) or [this one](<#The Foo type>),
+//! point to the same section, and so does the encoded link to [the café](#caf%C3%A9).
+//!
+//! Links to anchors that are not headings, like [this raw anchor](#MyAnchor) or
+//! [this version](#v1.2), are kept as they are.
+//!
+//! # The `Foo` type
+//!
+//! Nothing to see here.
+//!
+//! # Café
+//!
+//! <a id="MyAnchor"></a>Nothing to see here either.
@@ -0,0 +1,5 @@
+//! The [`Visible`] type gets a link, but neither [`Hidden`] nor [`Hidden::new`] do.
+
+pub struct Visible;
+
+pub struct Hidden;
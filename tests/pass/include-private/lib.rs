@@ -0,0 +1,10 @@
+//! This crate uses the private [`Inner`] type and the [`helpers::parse`] function to
+//! implement [`Outer`].
+
+pub struct Outer(Inner);
+
+struct Inner;
+
+mod helpers {
+	pub(crate) fn parse() {}
+}
@@ -0,0 +1,4 @@
+#![doc = include_str!("doc/intro.md")]
+#![doc = include_str!("doc/usage.md")]
+
+pub struct Foo;
@@ -0,0 +1,3 @@
+#![doc = include_str!("doc/intro.md")]
+#![doc = include_str!(concat!("doc/", "usage.md"))]
+#![doc = include_str!("doc/missing.md")]
@@ -0,0 +1,6 @@
+//! The word `indented` appears before the code block:
+//!
+//!     indented
+//!
+//! An attribute can also contain an indented code block.
+#![doc = "\n    also indented"]
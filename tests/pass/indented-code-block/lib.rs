@@ -0,0 +1,5 @@
+//! This crate has a doc comment with some accidentally indented prose.
+//!
+//!     This sentence should have been a paragraph.
+//!
+//! The rest of the documentation is fine.
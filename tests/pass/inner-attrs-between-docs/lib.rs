@@ -0,0 +1,17 @@
+#![warn(missing_docs)]
+//! The first paragraph of the documentation.
+//!
+//! ```rust
+//! let indented = true;
+//! ```
+#![feature(doc_cfg)]
+//! The second paragraph, after an inner attribute.
+#![allow(dead_code)]
+#![cfg_attr(docsrs, feature(doc_auto_cfg))]
+//!
+//!  - a list item
+//!  - another list item
+#![forbid(unsafe_code)]
+
+/// An item.
+pub struct Item;
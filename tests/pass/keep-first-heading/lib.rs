@@ -0,0 +1,11 @@
+//! # Keep First Heading
+//!
+//! The first heading of this crate becomes the title of the readme.
+//!
+//! # Usage
+//!
+//! All other headings are still increased by one level.
+//!
+//! ## Details
+//!
+//! Including nested ones.
@@ -0,0 +1 @@
+//! This crate has keywords and categories.
@@ -0,0 +1,3 @@
+//! Fully qualified paths like [`::std::option::Option`], [`::std::vec::Vec<u8>`] and
+//! [`::core::mem::swap`] link to the standard library, just like the paths [`Option`] and
+//! [`Vec`] from the prelude.
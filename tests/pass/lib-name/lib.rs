@@ -0,0 +1,6 @@
+//! This crate's library is called [`custom`] instead of `lib_name`. Links to items like
+//! [`Foo`], [`crate::Bar`] and [`custom::Foo`] should point to the docs of `custom`.
+
+pub struct Foo;
+
+pub struct Bar;
@@ -0,0 +1 @@
+//! This crate links to [`Vec`] and [`Option`].
@@ -0,0 +1,8 @@
+//! This crate links to [`String`], [`Vec`], [`Option`], [`Result`], [`Box`],
+//! [`Clone`], [`Copy`], [`Default`], [`Debug`](std::fmt::Debug), [`HashMap`] and
+//! [Rust](https://www.rust-lang.org "The Rust Programming Language").
+//!
+//! ![Logo][logo]
+//!
+//!  [`HashMap`]: std::collections::HashMap
+//!  [logo]: https://www.rust-lang.org/logos/rust-logo-128x128.png
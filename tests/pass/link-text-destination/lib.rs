@@ -0,0 +1,15 @@
+//! The text of [the config struct](crate::Config) is kept while the destination is
+//! resolved, also for [`code text`](Config::new), [reference links][the config] and
+//! [shortcut links with a different text][Config].
+//!
+//! [the config]: self::Config
+
+/// The config.
+pub struct Config;
+
+impl Config {
+	/// Create a new config.
+	pub fn new() -> Self {
+		Self
+	}
+}
@@ -0,0 +1,9 @@
+//! This crate has [a link with a title](https://example.org "The \"Example\" Website") and
+//! a [reference link][example] with a title. The [`Foo`] type has no title.
+//!
+//! ![Logo][logo]
+//!
+//! [example]: https://example.com 'Another Example'
+//! [logo]: https://example.org/logo.png "The Logo"
+
+pub struct Foo;
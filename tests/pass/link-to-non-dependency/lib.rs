@@ -0,0 +1,2 @@
+//! This crate does not depend on tokio, so linking to [`tokio::spawn`] is most likely
+//! a mistake.
@@ -0,0 +1,12 @@
+//! Only the definitions of the links to [`Item`], [`Item::new`], [`Vec`] and
+//! [the docs](https://docs.rs) are written.
+
+/// An item.
+pub struct Item;
+
+impl Item {
+	/// Create a new item.
+	pub fn new() -> Self {
+		Self
+	}
+}
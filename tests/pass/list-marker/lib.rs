@@ -0,0 +1,8 @@
+//! Lists can use a different marker:
+//!
+//! - first item
+//! - second item
+//!   * nested item
+//!
+//! 1. ordered lists
+//! 2. are not affected
@@ -0,0 +1,17 @@
+//! This crate exports the [`r#async!`], [`bracketed!`] and [`inner!`] macros.
+
+#[macro_export]
+macro_rules! r#async {
+	() => {};
+}
+
+#[macro_export(local_inner_macros)]
+macro_rules! bracketed [
+	() => { inner!() };
+];
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! inner {
+	() => {};
+}
@@ -0,0 +1,11 @@
+//! Links to [`A`], [`B`] and [`C`] all resolve, as the features from the package
+//! metadata alone do not exclude any items.
+
+#[cfg(feature = "a")]
+pub struct A;
+
+#[cfg(feature = "b")]
+pub struct B;
+
+#[cfg(feature = "c")]
+pub struct C;
@@ -0,0 +1,11 @@
+//! Links to [`A`] and [`B`] resolve as the features from the command line and the
+//! package metadata are merged, but links to [`C`] do not.
+
+#[cfg(feature = "a")]
+pub struct A;
+
+#[cfg(feature = "b")]
+pub struct B;
+
+#[cfg(feature = "c")]
+pub struct C;
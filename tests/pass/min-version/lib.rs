@@ -0,0 +1 @@
+//! The features documented here require a newer version of this crate.
@@ -0,0 +1,10 @@
+//! The module [`outer`] is a re-export of a private module, and contains [`outer::Thing`]
+//! and [`outer::LIMIT`].
+
+mod inner {
+	pub struct Thing;
+
+	pub const LIMIT: usize = 42;
+}
+
+pub use self::inner as outer;
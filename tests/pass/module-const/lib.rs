@@ -0,0 +1,13 @@
+//! The default value is [`crate::config::DEFAULT`], the limit is [`config::LIMIT`] and
+//! the value can be overridden in [`config::Config`].
+
+pub mod config {
+	/// The default value.
+	pub const DEFAULT: u32 = 42;
+
+	/// The upper limit.
+	pub static LIMIT: u32 = 100;
+
+	/// The configuration.
+	pub struct Config;
+}
@@ -0,0 +1,13 @@
+//! Your documentation can contain code blocks like this:
+//!
+//! ````markdown
+//! ```rust
+//! let answer = 42;
+//! ```
+//! ````
+//!
+//! Regular code blocks are still supported:
+//!
+//! ```
+//! let answer = 42;
+//! ```
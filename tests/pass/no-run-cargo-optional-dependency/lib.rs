@@ -0,0 +1,4 @@
+//! With all features enabled, [`Foo`] implements [`serde::Serialize`].
+
+#[cfg(feature = "serde")]
+pub struct Foo;
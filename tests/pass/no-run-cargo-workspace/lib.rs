@@ -0,0 +1,2 @@
+//! This crate inherits its version, edition, rust version, license and its dependency on
+//! [`serde`] from the workspace.
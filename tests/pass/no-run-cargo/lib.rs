@@ -0,0 +1,3 @@
+//! This crate implements [`Foo`], which can be serialized using [`serde::Serialize`].
+
+pub struct Foo;
@@ -0,0 +1,11 @@
+//! Text like 2 * 3 * 4, snake_case_names, #hashtags, \[brackets\], a+b, x^2 and {attribute}
+//! must not be formatted, and neither must the text of a [link with a * star][Item].
+//!
+//! Inline code like `a + b` can contain a plus.
+//!
+//! ```rust
+//! let [a, b] = [1 * 2, 3_000];
+//! ```
+
+/// An item.
+pub struct Item;
@@ -0,0 +1,26 @@
+//! This readme is rendered with *emphasis*, **strong text**, `code` and a link to
+//! [`Item`].
+//!
+//! # Usage
+//!
+//! ```rust
+//! let item = Item;
+//! ```
+//!
+//!  - an item
+//!  - another item
+//!    1. first
+//!    2. second
+//!
+//! > A quote.
+//!
+//! | Name | Value |
+//! |------|-------|
+//! | a    | 1     |
+//!
+//! ---
+//!
+//! ![An image](https://example.org/image.png)
+
+/// An item.
+pub struct Item;
@@ -0,0 +1,8 @@
+#![cfg_attr(
+	debug_assertions,
+	doc = "This readme was generated from a debug build."
+)]
+#![cfg_attr(
+	not(debug_assertions),
+	doc = "This readme was generated from a release build."
+)]
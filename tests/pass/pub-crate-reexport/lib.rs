@@ -0,0 +1,18 @@
+//! This crate re-exports [`Foo`], [`Bar`] and [`Baz`] from modules that are only
+//! visible inside the crate.
+
+pub(crate) mod foo {
+	pub struct Foo;
+}
+
+pub(crate) mod bar {
+	pub struct Bar;
+}
+
+pub(self) mod baz {
+	pub struct Baz;
+}
+
+pub use self::foo::Foo;
+pub use crate::bar::Bar;
+pub use baz::Baz;
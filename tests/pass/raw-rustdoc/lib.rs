@@ -0,0 +1,3 @@
+//! # Heading
+//!
+//! This crate links to [`Option`].
@@ -0,0 +1,3 @@
+//! This crate re-exports [`Serialize`] and [`Deserialize`] from serde.
+
+pub use serde::{de::Deserialize, Serialize};
@@ -0,0 +1,15 @@
+//! This crate re-exports some items from its private modules.
+
+mod private {
+	pub struct Foo;
+	pub struct Bar;
+}
+
+/// The [`Foo`] type is the main entry point of this crate.
+pub use private::Foo;
+
+/// This comment is not part of the readme as the re-export is private.
+use private::Bar as _;
+
+/// The [`Bar`] type is also very useful.
+pub use private::Bar;
@@ -0,0 +1,6 @@
+//! ![Logo][logo]
+//!
+//! This crate has a logo, referenced like an image. Inline images like
+//! ![this one](https://example.org/inline.png) stay inline.
+//!
+//! [logo]: https://example.org/logo.png
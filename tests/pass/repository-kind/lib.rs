@@ -0,0 +1 @@
+//! The template can use the parts of the repository url.
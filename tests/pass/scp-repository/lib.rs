@@ -0,0 +1 @@
+//! The repository is an scp-like git url, but is still recognized as GitHub.
@@ -0,0 +1 @@
+//! The template links to the docs.rs and crates.io pages of this crate.
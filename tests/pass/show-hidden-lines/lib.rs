@@ -0,0 +1,17 @@
+//! The full example is shown, including the lines that are hidden in the documentation:
+//!
+//! ```
+//! # use std::collections::HashMap;
+//! #
+//! # fn main() {
+//! let mut map = HashMap::new();
+//! map.insert("key", 1);
+//! #[allow(unused_variables)]
+//! let value = map["key"];
+//! # }
+//! ```
+//!
+//! ```ignore
+//! // lines of ignored code blocks are never changed
+//! # let hidden = true;
+//! ```
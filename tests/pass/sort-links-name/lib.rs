@@ -0,0 +1,15 @@
+//! This crate links to [`A`], [`B`], [`C`], [`D`], [`E`], [`F`], [`G`], [`H`], [`I`],
+//! [`J`], [`K`] and [`L`].
+
+pub struct A;
+pub struct B;
+pub struct C;
+pub struct D;
+pub struct E;
+pub struct F;
+pub struct G;
+pub struct H;
+pub struct I;
+pub struct J;
+pub struct K;
+pub struct L;
@@ -0,0 +1,8 @@
+//! This crate has a [`Zebra`], an [`Ant`] and a [`Monkey`]. Sometimes, they
+//! visit [crates.io](https://crates.io) together.
+
+pub struct Ant;
+
+pub struct Monkey;
+
+pub struct Zebra;
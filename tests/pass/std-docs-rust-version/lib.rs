@@ -0,0 +1,3 @@
+//! Links to the standard library like [`String`] and [`Option::map`] depend on the
+//! `rust-version` of the crate. A `rust-version` of `1.74` links to the documentation
+//! of Rust `1.74.0`, as old releases are only published under their full version.
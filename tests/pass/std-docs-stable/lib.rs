@@ -0,0 +1,2 @@
+//! Links to the standard library like [`String`] and [`Option::map`] depend on the
+//! `rust-version` of the crate.
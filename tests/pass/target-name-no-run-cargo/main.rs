@@ -0,0 +1,3 @@
+//! This is the documentation of the binary.
+
+fn main() {}
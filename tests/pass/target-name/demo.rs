@@ -0,0 +1,3 @@
+//! This is the documentation of the `demo` example, which uses [`Vec`].
+
+fn main() {}
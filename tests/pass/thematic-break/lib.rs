@@ -0,0 +1,9 @@
+//! Some text before the break.
+//!
+//! ---
+//!
+//! Some text after the break.
+//!
+//! ___
+//!
+//! The end.
@@ -0,0 +1 @@
+//! The title of this readme contains the crate version.
@@ -0,0 +1 @@
+//! This crate uses the default template with a custom title.
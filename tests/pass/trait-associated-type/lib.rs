@@ -0,0 +1,11 @@
+//! The [`Shape::Unit`] of a [`Shape`] is an associated type, like [`Iterator::Item`],
+//! while [`Shape::area`] is a method.
+
+/// A shape.
+pub trait Shape {
+	/// The unit of the area.
+	type Unit;
+
+	/// Return the area of the shape.
+	fn area(&self) -> Self::Unit;
+}
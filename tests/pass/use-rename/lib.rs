@@ -0,0 +1,4 @@
+//! This crate re-uses the [`S`] trait from serde under a shorter name.
+
+#[allow(unused_imports)]
+use serde::Serialize as S;
@@ -0,0 +1,14 @@
+//! This crate has an example that compiles.
+//!
+//! ```rust
+//! assert_eq!(verify_doctests::answer(), 42);
+//! ```
+//!
+//! ```rust,ignore
+//! this is not valid rust code
+//! ```
+
+/// The answer.
+pub fn answer() -> u32 {
+	42
+}
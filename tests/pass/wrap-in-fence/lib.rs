@@ -0,0 +1,10 @@
+//! The readme is wrapped in a fence that is longer than the fence of this example:
+//!
+//! ```rust
+//! let answer = 42;
+//! ```
+//!
+//! The link to [`Item`] is still resolved.
+
+/// An item.
+pub struct Item;
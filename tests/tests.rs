@@ -3,8 +3,18 @@
 #![forbid(unsafe_code)]
 
 use cargo_doc2readme::{
-	diagnostic::Diagnostic, input::InputFile, output, read_input, verify
+	depinfo::DependencyInfo,
+	diagnostic::Diagnostic,
+	input::InputFile,
+	output::{
+		self, CodeblockFlags, EmitOptions, EmphasisChar, LinkStyle, ListMarker,
+		OutputFormat, SortLinks, ThematicBreak
+	},
+	read_input,
+	verify::{self, Check, CheckDepMode, CheckOptions},
+	InputOptions
 };
+use cargo_metadata::Edition;
 use lazy_regex::regex_replace_all;
 use libtest::{Arguments, Failed, Trial};
 use pretty_assertions::Comparison;
@@ -52,17 +62,152 @@ struct TestConfig {
 	#[serde(default)]
 	expand_macros: bool,
 
-	/// Test with these features enabled. Ignored unless combined with `--expand-macros`.
+	/// Test with these features enabled.
 	features: Option<String>,
 
-	/// Test with all features enabled. Ignored unless combined with `--expand-macros`.
+	/// Test with macros expanded using this cargo profile.
+	profile: Option<String>,
+
+	/// Test with all features enabled.
 	#[serde(default)]
 	all_features: bool,
 
-	/// Test without default feature being enabled. Ignored unless combined with
-	/// `--expand-macros`.
+	/// Test without default feature being enabled.
+	#[serde(default)]
+	no_default_features: bool,
+
+	/// Test as if `--no-run-cargo` was passed.
+	#[serde(default)]
+	no_run_cargo: bool,
+
+	/// Test as if `--include-reexport-docs` was passed.
+	#[serde(default)]
+	include_reexport_docs: bool,
+
+	/// Test as if `--fallback-to-item` was passed.
+	#[serde(default)]
+	fallback_to_item: bool,
+
+	/// Test as if `--include-private` was passed.
+	#[serde(default)]
+	include_private: bool,
+
+	/// Test as if `--allow-glob-use` was passed.
+	#[serde(default)]
+	allow_glob_use: bool,
+
+	/// Test with the target of this name.
+	target_name: Option<String>,
+
+	/// Test with the rustdoc read from the binary target with this name.
+	bin_name: Option<String>,
+
+	/// Test with the target of this crate type.
+	crate_type: Option<String>,
+
+	/// Test with the documentation of this module.
+	doc_from: Option<String>,
+
+	/// Test with the prelude of this edition.
+	edition: Option<Edition>,
+
+	/// Test as if `--verify-doctests` was passed.
+	#[serde(default)]
+	verify_doctests: bool,
+
+	/// Test with the link definitions sorted.
+	sort_links: Option<SortLinks>,
+
+	/// Test with this style of link reference names.
+	#[serde(default)]
+	link_style: LinkStyle,
+
+	/// Test with this marker for unordered lists.
+	#[serde(default)]
+	list_marker: ListMarker,
+
+	/// Test with this character for emphasis.
+	#[serde(default)]
+	emphasis_char: EmphasisChar,
+
+	/// Test with this style of thematic breaks.
+	#[serde(default)]
+	thematic_break: ThematicBreak,
+
+	/// Test with this policy for the flags of code blocks.
+	#[serde(default)]
+	codeblock_flags: CodeblockFlags,
+
+	/// Test with the readme wrapped in a fenced code block with this language.
+	wrap_in_fence: Option<String>,
+
+	/// Test with this output format.
+	#[serde(default)]
+	output_format: OutputFormat,
+
+	/// Test as if `--keep-first-heading` was passed.
+	#[serde(default)]
+	keep_first_heading: bool,
+
+	/// Test as if `--dedup-title` was passed.
+	#[serde(default)]
+	dedup_title: bool,
+
+	/// Test as if `--bare` was passed.
+	#[serde(default)]
+	bare: bool,
+
+	/// Test as if `--links-only` was passed.
+	#[serde(default)]
+	links_only: bool,
+
+	/// Test as if `--show-hidden-lines` was passed.
+	#[serde(default)]
+	show_hidden_lines: bool,
+
+	/// Test as if `--emit-content-hash` was passed.
+	#[serde(default)]
+	emit_content_hash: bool,
+
+	/// Test with this title format.
+	title_format: Option<String>,
+
+	/// Test with this base url for links to crates.
+	crates_io_base_url: Option<String>,
+
+	/// Test with this base url for links to the docs of crates.
+	docs_rs_base_url: Option<String>,
+
+	/// Test with this base url for links to the standard library.
+	std_docs_base_url: Option<String>,
+
+	/// Test as if `--std-docs-stable` was passed.
+	#[serde(default)]
+	std_docs_stable: bool,
+
+	/// Test with the dependency info stored in `README.md.doc2readme`.
+	#[serde(default)]
+	dep_info_sidecar: bool,
+
+	/// Test that `--dump-input` matches the content of `input.json`.
+	#[serde(default)]
+	dump_input: bool,
+
+	/// Test that `--emit-anchors` matches the content of `anchors.json`.
+	#[serde(default)]
+	emit_anchors: bool,
+
+	/// Check the readme with this dependency version mode.
+	#[serde(default)]
+	check_dep_mode: CheckDepMode,
+
+	/// Only check the region between the markers of the readme.
+	#[serde(default)]
+	markers: bool,
+
+	/// Check the content of the readme without the dependency info.
 	#[serde(default)]
-	no_default_features: bool
+	ignore_dep_info: bool
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -93,15 +238,32 @@ fn sanitize_stderr(stderr: Vec<u8>) -> anyhow::Result<String> {
 	Ok(regex_replace_all!("\x1B\\[[^m]+m", &stderr, |_| "").into_owned())
 }
 
+/// Compare the diagnostics with the content of `stderr.log`.
+fn check_stderr(stderr_path: &Path, stderr: String) -> Result<(), Failed> {
+	if stderr_path.exists() {
+		let expected = fs::read_to_string(stderr_path)?;
+		assert_eq!(expected, stderr)?;
+		Ok(())
+	} else if !stderr.trim().is_empty() {
+		fs::write(stderr_path, stderr.as_bytes())?;
+		Err("WIP".into())
+	} else {
+		Err("Missing diagnostics".into())
+	}
+}
+
 struct TestRun<'a> {
 	data: &'a TestData,
 
 	readme_path: PathBuf,
+	dep_info_path: PathBuf,
 	stderr_path: PathBuf,
 
 	input_file: InputFile,
 	template: Cow<'static, str>,
-	diagnostic: Diagnostic
+	diagnostic: Diagnostic,
+	options: EmitOptions,
+	check_options: CheckOptions
 }
 
 impl<'a> TestRun<'a> {
@@ -111,26 +273,81 @@ impl<'a> TestRun<'a> {
 		let parent = manifest_path.parent().unwrap();
 		let template_path = parent.join("README.j2");
 		let readme_path = parent.join("README.md");
+		let dep_info_path = DependencyInfo::sidecar_path(&readme_path);
 		let stderr_path = parent.join("stderr.log");
 
-		let (input_file, template, diagnostic) = read_input(
-			Some(manifest_path),
-			None,
-			false,
-			data.config.expand_macros,
-			template_path,
-			data.config.features.clone(),
-			data.config.no_default_features,
-			data.config.all_features
-		);
+		let (input_file, template, diagnostic) = read_input(InputOptions {
+			manifest_path: Some(manifest_path),
+			expand_macros: data.config.expand_macros,
+			template: template_path,
+			features: data.config.features.clone(),
+			profile: data.config.profile.clone(),
+			no_default_features: data.config.no_default_features,
+			all_features: data.config.all_features,
+			no_run_cargo: data.config.no_run_cargo,
+			include_reexport_docs: data.config.include_reexport_docs,
+			fallback_to_item: data.config.fallback_to_item,
+			include_private: data.config.include_private,
+			allow_glob_use: data.config.allow_glob_use,
+			verify_doctests: data.config.verify_doctests,
+			edition: data.config.edition,
+			target_name: data.config.target_name.clone(),
+			bin_name: data.config.bin_name.clone(),
+			crate_type: data.config.crate_type.clone(),
+			doc_from: data.config.doc_from.clone(),
+			..Default::default()
+		});
+		let options = EmitOptions {
+			sort_links: data.config.sort_links,
+			keep_first_heading: data.config.keep_first_heading,
+			dedup_title: data.config.dedup_title,
+			title_format: data
+				.config
+				.title_format
+				.as_deref()
+				.map(|format| format.parse().unwrap()),
+			bare: data.config.bare,
+			links_only: data.config.links_only,
+			show_hidden_lines: data.config.show_hidden_lines,
+			emit_content_hash: data.config.emit_content_hash,
+			crates_io_base_url: data.config.crates_io_base_url.clone(),
+			docs_rs_base_url: data.config.docs_rs_base_url.clone(),
+			std_docs_base_url: data.config.std_docs_base_url.clone(),
+			std_docs_stable: data.config.std_docs_stable,
+			link_style: data.config.link_style,
+			list_marker: data.config.list_marker,
+			emphasis_char: data.config.emphasis_char,
+			thematic_break: data.config.thematic_break,
+			codeblock_flags: data.config.codeblock_flags,
+			wrap_in_fence: data.config.wrap_in_fence.clone(),
+			output_format: data.config.output_format,
+			..Default::default()
+		};
+		let check_options = CheckOptions {
+			dep_mode: data.config.check_dep_mode,
+			markers: data.config.markers,
+			ignore_dep_info: data.config.ignore_dep_info
+		};
 
 		Self {
 			data,
 			readme_path,
+			dep_info_path,
 			stderr_path,
 			input_file,
 			template,
-			diagnostic
+			diagnostic,
+			options,
+			check_options
+		}
+	}
+
+	/// Open the dependency info sidecar file, if it exists.
+	fn open_dep_info(&self) -> io::Result<Option<File>> {
+		match File::open(&self.dep_info_path) {
+			Ok(file) => Ok(Some(file)),
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+			Err(err) => Err(err)
 		}
 	}
 
@@ -141,17 +358,7 @@ impl<'a> TestRun<'a> {
 	}
 
 	fn check_stderr(&self) -> Result<(), Failed> {
-		let stderr = self.collect_stderr()?;
-		if self.stderr_path.exists() {
-			let expected = fs::read_to_string(&self.stderr_path)?;
-			assert_eq!(expected, stderr)?;
-			Ok(())
-		} else if !stderr.trim().is_empty() {
-			fs::write(&self.stderr_path, stderr.as_bytes())?;
-			Err("WIP".into())
-		} else {
-			Err("Missing diagnostics".into())
-		}
+		check_stderr(&self.stderr_path, self.collect_stderr()?)
 	}
 
 	/// Run this to check if the generated readme (and diagnostics) match the expected
@@ -165,22 +372,101 @@ impl<'a> TestRun<'a> {
 			.into());
 		}
 
-		if self.data.config.stderr {
-			self.check_stderr()?;
+		// diagnostics from reading the input are followed by those from emitting the readme
+		let mut stderr = self.collect_stderr()?;
+		let mut diagnostic = self.diagnostic.empty_clone();
+
+		// the dump produces the same diagnostics as emitting the readme, so we ignore them
+		let mut actual_dump = Vec::<u8>::new();
+		if self.data.config.dump_input {
+			output::dump_input(
+				&self.input_file,
+				&self.template,
+				&self.options,
+				&mut actual_dump,
+				&mut Diagnostic::new(String::new(), String::new())
+			)?;
 		}
 
-		let mut actual = Vec::<u8>::new();
-		output::emit(self.input_file, &self.template, &mut actual)?;
+		let mut actual_anchors = Vec::<u8>::new();
+		if self.data.config.emit_anchors {
+			output::emit_anchors(
+				&self.input_file,
+				&self.template,
+				&self.options,
+				&mut actual_anchors
+			)?;
+		}
 
+		let mut actual = Vec::<u8>::new();
+		let mut actual_dep_info = Vec::<u8>::new();
+		output::emit(
+			self.input_file,
+			&self.template,
+			&self.options,
+			&mut actual,
+			self.data
+				.config
+				.dep_info_sidecar
+				.then_some(&mut actual_dep_info as _),
+			&mut diagnostic
+		)?;
+
+		let mut wip = false;
 		if self.readme_path.exists() {
 			let actual = String::from_utf8(actual)?;
 			let expected = fs::read_to_string(&self.readme_path)?;
 			assert_eq!(expected, actual)?;
 		} else {
 			fs::write(&self.readme_path, &actual)?;
-			return Err("WIP".into());
+			wip = true;
+		}
+
+		if self.data.config.dep_info_sidecar {
+			if self.dep_info_path.exists() {
+				let actual = String::from_utf8(actual_dep_info)?;
+				let expected = fs::read_to_string(&self.dep_info_path)?;
+				assert_eq!(expected, actual)?;
+			} else {
+				fs::write(&self.dep_info_path, &actual_dep_info)?;
+				wip = true;
+			}
+		}
+
+		if self.data.config.dump_input {
+			let dump_path = self.readme_path.with_file_name("input.json");
+			if dump_path.exists() {
+				let actual = String::from_utf8(actual_dump)?;
+				let expected = fs::read_to_string(&dump_path)?;
+				assert_eq!(expected, actual)?;
+			} else {
+				fs::write(&dump_path, &actual_dump)?;
+				wip = true;
+			}
+		}
+
+		if self.data.config.emit_anchors {
+			let anchors_path = self.readme_path.with_file_name("anchors.json");
+			if anchors_path.exists() {
+				let actual = String::from_utf8(actual_anchors)?;
+				let expected = fs::read_to_string(&anchors_path)?;
+				assert_eq!(expected, actual)?;
+			} else {
+				fs::write(&anchors_path, &actual_anchors)?;
+				wip = true;
+			}
+		}
+
+		if self.data.config.stderr {
+			let mut emit_stderr = Vec::new();
+			diagnostic.print_to(&mut emit_stderr).unwrap();
+			stderr += &sanitize_stderr(emit_stderr)?;
+			check_stderr(&self.stderr_path, stderr)?;
 		}
 
+		if wip {
+			return Err("WIP".into());
+		}
 		Ok(())
 	}
 
@@ -210,13 +496,25 @@ impl<'a> TestRun<'a> {
 		}
 
 		if self.readme_path.exists() {
-			let mut file = File::open(self.readme_path)?;
-			let check =
-				verify::check_up2date(self.input_file, &self.template, &mut file)?;
-			if check.is_ok() {
+			let mut file = File::open(&self.readme_path)?;
+			let mut dep_info_file = self.open_dep_info()?;
+			let check = verify::check_up2date(
+				self.input_file,
+				&self.template,
+				&self.options,
+				&self.check_options,
+				&mut file,
+				dep_info_file.as_mut().map(|file| file as _)
+			)?;
+			if !check.is_ok() {
+				Err("Expected check to pass, but it failed".into())
+			} else if matches!(check, Check::UpToDate) {
 				Ok(())
 			} else {
-				Err("Expected check to pass, but it failed".into())
+				// the check passed with a warning
+				let mut stderr = Vec::new();
+				check.print_to("README.md", &mut stderr).unwrap();
+				check_stderr(&self.stderr_path, sanitize_stderr(stderr)?)
 			}
 		} else {
 			Err("WIP".into())
@@ -226,9 +524,16 @@ impl<'a> TestRun<'a> {
 	fn check_check_fail(self) -> Result<(), Failed> {
 		if !self.diagnostic.is_fail() {
 			return if self.readme_path.exists() {
-				let mut file = File::open(self.readme_path)?;
-				let check =
-					verify::check_up2date(self.input_file, &self.template, &mut file)?;
+				let mut file = File::open(&self.readme_path)?;
+				let mut dep_info_file = self.open_dep_info()?;
+				let check = verify::check_up2date(
+					self.input_file,
+					&self.template,
+					&self.options,
+					&self.check_options,
+					&mut file,
+					dep_info_file.as_mut().map(|file| file as _)
+				)?;
 				if check.is_ok() {
 					Err("Expected check to fail, but it passed".into())
 				} else {
@@ -344,6 +649,7 @@ fn main() -> anyhow::Result<()> {
 	add_tests_from_dir(&mut tests, "tests/pass", [ReadmePass, CheckPass], true)?;
 	add_tests_from_dir(&mut tests, "tests/fail", [ReadmeFail], true)?;
 	add_tests_from_dir(&mut tests, "tests/check", [CheckFail], true)?;
+	add_tests_from_dir(&mut tests, "tests/check-pass", [CheckPass], true)?;
 
 	libtest::run(&args, tests).exit()
 }